@@ -0,0 +1,47 @@
+use std::cmp::Ordering;
+use std::ops::Bound;
+
+/// Decides the order of keys in a skiplist.
+///
+/// Any `Fn(&T, &T) -> Ordering` is a comparator. Lookups by a borrowed form
+/// `Q` of the key need a comparator for `Q` as well, which [`OrdComparator`]
+/// provides for every `Q: Ord`.
+pub trait Comparator<T: ?Sized> {
+    fn compare(&self, k1: &T, k2: &T) -> Ordering;
+}
+
+impl<T, F> Comparator<T> for F
+where
+    T: ?Sized,
+    F: Fn(&T, &T) -> Ordering,
+{
+    fn compare(&self, k1: &T, k2: &T) -> Ordering {
+        self(k1, k2)
+    }
+}
+
+/// Orders keys by their [`Ord`] implementation. Used by `SkipList::new`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OrdComparator;
+
+impl<T> Comparator<T> for OrdComparator
+where
+    T: Ord + ?Sized,
+{
+    fn compare(&self, k1: &T, k2: &T) -> Ordering {
+        k1.cmp(k2)
+    }
+}
+
+/// Whether a key lies past the end `bound` of a range, given how it compares
+/// against the bound's key.
+pub(crate) fn past_end<Q>(bound: Bound<&Q>, cmp: impl FnOnce(&Q) -> Ordering) -> bool
+where
+    Q: ?Sized,
+{
+    match bound {
+        Bound::Included(end) => cmp(end).is_gt(),
+        Bound::Excluded(end) => cmp(end).is_ge(),
+        Bound::Unbounded => false,
+    }
+}
@@ -0,0 +1,85 @@
+use crate::{OrdComparator, XorShiftGenerator};
+
+/// The most levels a skiplist can be configured with.
+pub const MAX_LEVEL: usize = 32;
+
+/// Settings for a new skiplist: the promotion probability, the number of
+/// levels, the comparator and the level generator. Pass it to
+/// `SkipList::with_config`.
+#[derive(Clone, Copy, Debug)]
+pub struct SkipListConfig<C = OrdComparator, G = XorShiftGenerator> {
+    pub(crate) p: f32,
+    pub(crate) max_level: usize,
+    pub(crate) cmp: C,
+    pub(crate) level_generator: G,
+}
+
+impl SkipListConfig {
+    /// Returns the default settings: `p` of 0.5, [`MAX_LEVEL`] levels, keys
+    /// ordered by [`Ord`] and levels drawn by a [`XorShiftGenerator`].
+    pub fn new() -> Self {
+        SkipListConfig {
+            p: 0.5,
+            max_level: MAX_LEVEL,
+            cmp: OrdComparator,
+            level_generator: XorShiftGenerator,
+        }
+    }
+}
+
+impl Default for SkipListConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C, G> SkipListConfig<C, G> {
+    /// Sets the probability that an entry reaching a level also reaches the
+    /// next one. It must lie strictly between 0 and 1.
+    pub fn p(mut self, p: f32) -> Self {
+        self.p = p;
+        self
+    }
+
+    /// Sets how many levels the list has, from 1 up to [`MAX_LEVEL`].
+    pub fn max_level(mut self, max_level: usize) -> Self {
+        self.max_level = max_level;
+        self
+    }
+
+    /// Sets the comparator that orders the keys.
+    pub fn cmp<D>(self, cmp: D) -> SkipListConfig<D, G> {
+        SkipListConfig {
+            p: self.p,
+            max_level: self.max_level,
+            cmp,
+            level_generator: self.level_generator,
+        }
+    }
+
+    /// Sets the generator that draws the levels of new entries, such as a
+    /// [`SeededGenerator`](crate::SeededGenerator) to reproduce the shape
+    /// of a list.
+    pub fn level_generator<H>(self, level_generator: H) -> SkipListConfig<C, H> {
+        SkipListConfig {
+            p: self.p,
+            max_level: self.max_level,
+            cmp: self.cmp,
+            level_generator,
+        }
+    }
+
+    /// Panics unless `p` and `max_level` lie within their bounds.
+    pub(crate) fn validate(&self) {
+        assert!(
+            self.p > 0. && self.p < 1.,
+            "p must lie strictly between 0 and 1, got {}",
+            self.p
+        );
+        assert!(
+            (1..=MAX_LEVEL).contains(&self.max_level),
+            "max_level must lie between 1 and {MAX_LEVEL}, got {}",
+            self.max_level
+        );
+    }
+}
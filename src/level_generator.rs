@@ -0,0 +1,89 @@
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+
+/// Draws the top levels of new entries, which decide the shape of the
+/// towers.
+///
+/// Implementors only supply random bits; [`random_level`] turns them into
+/// levels, and may be overridden for another distribution.
+///
+/// [`random_level`]: LevelGenerator::random_level
+pub trait LevelGenerator {
+    /// Returns 64 random bits.
+    fn next_u64(&self) -> u64;
+
+    /// Draws the top level of a new entry: each level is reached with
+    /// probability `p` from the one below, and the top level is below
+    /// `max_level`.
+    fn random_level(&self, p: f32, max_level: usize) -> usize {
+        let top_level = max_level - 1;
+
+        // Each bit is a fair coin flip, so a single draw settles every level.
+        if p == 0.5 {
+            return (self.next_u64().trailing_zeros() as usize).min(top_level);
+        }
+
+        let threshold = (p as f64 * u64::MAX as f64) as u64;
+        let mut level = 0;
+
+        while level < top_level && self.next_u64() < threshold {
+            level += 1;
+        }
+
+        level
+    }
+}
+
+/// The default generator: a xorshift per thread, seeded from the thread's
+/// RNG the first time the thread draws. Never contended, and a few
+/// instructions per draw.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct XorShiftGenerator;
+
+impl LevelGenerator for XorShiftGenerator {
+    fn next_u64(&self) -> u64 {
+        thread_local! {
+            // Xorshift never leaves, nor reaches, the all-zero state.
+            static STATE: Cell<u64> = Cell::new(rand::random::<u64>() | 1);
+        }
+
+        STATE.with(|state| {
+            let mut x = state.get();
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            state.set(x);
+            x
+        })
+    }
+}
+
+/// A generator that draws the same sequence for the same seed, so that a
+/// list filled in the same order gets the same towers.
+///
+/// The sequence is SplitMix64, shared by every thread through one atomic
+/// counter. Concurrent inserts take its draws in whatever order they race,
+/// so only a single-threaded run repeats exactly.
+#[derive(Debug)]
+pub struct SeededGenerator {
+    state: AtomicU64,
+}
+
+impl SeededGenerator {
+    pub fn new(seed: u64) -> Self {
+        SeededGenerator {
+            state: AtomicU64::new(seed),
+        }
+    }
+}
+
+impl LevelGenerator for SeededGenerator {
+    fn next_u64(&self) -> u64 {
+        const GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+        let mut z = self.state.fetch_add(GAMMA, Relaxed).wrapping_add(GAMMA);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
@@ -0,0 +1,16 @@
+mod comparator;
+mod config;
+mod level_generator;
+pub mod lock_base;
+pub mod lock_free;
+mod multi_key;
+mod ordered_map;
+pub mod sequencial;
+mod striped_counter;
+mod value_slot;
+
+pub(crate) use comparator::past_end;
+pub use comparator::{Comparator, OrdComparator};
+pub use config::{SkipListConfig, MAX_LEVEL};
+pub use level_generator::{LevelGenerator, SeededGenerator, XorShiftGenerator};
+pub use ordered_map::{ConcurrentOrderedMap, OrderedMap};
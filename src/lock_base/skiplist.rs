@@ -1,297 +1,897 @@
-use std::fmt::Debug;
-use std::sync::atomic::{AtomicBool, Ordering::*};
-use std::sync::{Mutex, MutexGuard};
-use std::{
-    cmp::{min, Ordering},
-    ptr::null_mut,
-    sync::atomic::AtomicPtr,
-};
+use std::hint::spin_loop;
+use std::ops::{Bound, RangeBounds};
+use std::sync::atomic::{AtomicUsize, Ordering::*};
+use std::{borrow::Borrow, ptr::null_mut};
 
 use self::entry::{Entry, Key};
-use parking_lot::ReentrantMutex;
-use rand::random;
+use crate::striped_counter::StripedCounter;
+use crate::{Comparator, ConcurrentOrderedMap, OrdComparator, OrderedMap};
+use crate::{LevelGenerator, SkipListConfig};
+use crossbeam_epoch::{self as epoch, Guard, Owned, Shared};
+
+pub use self::entry_ref::EntryRef;
+pub use self::iter::Range;
 
 mod entry;
+mod entry_ref;
+mod iter;
+
+/// An entry found in the list together with the value it held when found.
+type Found<'g, K, V, C> = (*mut Entry<K, V, C>, Shared<'g, V>);
 
-pub struct SkipList<K, V, C = fn(&K, &K) -> Ordering>
+pub struct SkipList<K, V, C = OrdComparator>
 where
-    K: Ord,
-    C: Fn(&K, &K) -> Ordering,
+    C: Comparator<K>,
 {
     head: *mut Entry<K, V, C>,
     key_cmp: C,
-}
-
-fn default_cmp<K: Ord>(k1: &K, k2: &K) -> Ordering {
-    k1.cmp(k2)
+    len: StripedCounter,
+    p: f32,
+    level_generator: Box<dyn LevelGenerator + Send + Sync>,
+    /// How many levels the head has, which no entry exceeds.
+    max_level: usize,
+    /// How many levels, counted from the bottom, any entry has reached, so
+    /// that searches start there. Only ever raised, before an entry of that
+    /// height is searched for; see `find` for why a stale read is harmless.
+    current_height: AtomicUsize,
 }
 
 impl<K, V> SkipList<K, V>
 where
-    K: Ord + Debug,
+    K: Ord,
 {
     pub fn new() -> Self {
-        SkipList::with_cmp(default_cmp)
+        SkipList::with_cmp(OrdComparator)
+    }
+}
+
+impl<K, V> Default for SkipList<K, V>
+where
+    K: Ord,
+{
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 unsafe impl<K, V, C> Send for SkipList<K, V, C>
 where
-    K: Ord + Send,
+    K: Send,
     V: Send,
-    C: Fn(&K, &K) -> Ordering + Send,
+    C: Comparator<K> + Send,
 {
 }
 
 unsafe impl<K, V, C> Sync for SkipList<K, V, C>
 where
-    K: Ord + Sync,
-    V: Sync,
-    C: Fn(&K, &K) -> Ordering + Sync,
+    K: Send + Sync,
+    V: Send + Sync,
+    C: Comparator<K> + Sync,
 {
 }
 
 impl<K, V, C> SkipList<K, V, C>
 where
-    K: Ord + Debug,
-    C: Fn(&K, &K) -> Ordering,
+    C: Comparator<K>,
 {
     pub fn with_cmp(cmp: C) -> Self {
-        let head = Box::into_raw(Box::new(Entry {
-            key: Key::Head,
-            value: None,
-            lock: ReentrantMutex::new(()),
-            marked: AtomicBool::new(false),
-            fully_linked: AtomicBool::new(false),
-            top_level: MAX_LEVEL,
-            nexts: (1..=MAX_LEVEL).map(|_| AtomicPtr::default()).collect(),
-        }));
-
-        let tail = Box::into_raw(Box::new(Entry {
-            key: Key::Tail,
-            value: None,
-            lock: ReentrantMutex::new(()),
-            marked: AtomicBool::new(false),
-            fully_linked: AtomicBool::new(false),
-            top_level: MAX_LEVEL,
-            nexts: (1..=MAX_LEVEL).map(|_| AtomicPtr::default()).collect(),
-        }));
+        SkipList::with_config(SkipListConfig::new().cmp(cmp))
+    }
+
+    /// Creates a list with the promotion probability, number of levels,
+    /// comparator and level generator of `config`.
+    ///
+    /// # Panics
+    ///
+    /// If `config` holds a `p` or `max_level` out of bounds.
+    pub fn with_config<G>(config: SkipListConfig<C, G>) -> Self
+    where
+        G: LevelGenerator + Send + Sync + 'static,
+    {
+        config.validate();
+
+        let max_level = config.max_level;
+        let head = Entry::alloc(Key::Head, None, max_level - 1);
+        let tail = Entry::alloc(Key::Tail, None, max_level - 1);
 
         unsafe {
-            for level in 0..MAX_LEVEL {
-                (*head).nexts[level].store(tail, Relaxed);
+            for level in 0..max_level {
+                (*head).nexts()[level].store(tail, Relaxed);
             }
         }
 
-        SkipList { head, key_cmp: cmp }
+        SkipList {
+            head,
+            key_cmp: config.cmp,
+            len: StripedCounter::new(),
+            p: config.p,
+            level_generator: Box::new(config.level_generator),
+            max_level,
+            current_height: AtomicUsize::new(1),
+        }
     }
+}
 
+// Removed entries may be dropped by whichever thread happens to collect them,
+// and only after this call has returned.
+impl<K, V, C> SkipList<K, V, C>
+where
+    K: Send + 'static,
+    V: Send + 'static,
+    C: Comparator<K>,
+{
     pub fn add(&self, key: K, value: V) -> bool {
-        let top_level = random_level();
-        let mut preds = vec![null_mut(); MAX_LEVEL + 1];
-        let mut succs = vec![null_mut(); MAX_LEVEL + 1];
+        self.try_insert(key, value).is_ok()
+    }
 
-        let key = Key::Entry(key);
+    /// Inserts `key` with `value` unless it is present, in which case both
+    /// are handed back untouched.
+    pub fn try_insert(&self, key: K, value: V) -> Result<(), (K, V)> {
+        let guard = &epoch::pin();
+        let top_level = self.level_generator.random_level(self.p, self.max_level);
+        self.raise_height(top_level + 1);
+        let mut preds = vec![null_mut(); self.max_level];
+        let mut succs = vec![null_mut(); self.max_level];
+
+        let (mut key, mut value) = (key, value);
 
         loop {
-            let level_found = self.find(&key, &mut preds, &mut succs);
-
-            if level_found.is_some() {
-                unsafe {
-                    let entry_found = &*succs[level_found.unwrap()];
-                    if !entry_found.marked.load(Relaxed) {
-                        while !entry_found.fully_linked.load(Relaxed) {}
-                        return false;
-                    }
-                }
+            if self.search(&key, &mut preds, &mut succs, guard).is_some() {
+                return Err((key, value));
+            }
 
-                return false;
+            match self.link(key, value, top_level, &preds, &succs, guard) {
+                Ok(_) => return Ok(()),
+                Err((k, v)) => (key, value) = (k, v),
             }
+        }
+    }
 
-            let mut valid = true;
-            let mut guards = Vec::with_capacity(top_level + 1);
+    /// Inserts `key` with `value`, replacing the value already held for the
+    /// key, and returns a reference to the value it replaced.
+    ///
+    /// Like `BTreeMap::insert`, only the value is swapped: the entry keeps
+    /// the key it was inserted with and `key` is dropped. The swap is made
+    /// under the entry's lock, so a concurrent `remove` of the key either
+    /// removes the old value before it or the new one after it. As with
+    /// `remove`, other threads may still be reading the old value, so it
+    /// stays behind a reference and is reclaimed once that is dropped.
+    pub fn insert(&self, key: K, value: V) -> Option<EntryRef<'_, K, V>> {
+        let guard = epoch::pin();
+        let (entry, old_value) = self.replace(key, value, &guard)?;
 
-            for level in 0..=top_level {
-                unsafe {
-                    let pred = &mut *preds[level];
-                    let succ = &mut *succs[level];
+        unsafe {
+            guard.defer_destroy(old_value);
+            let old_value = old_value.as_raw();
 
-                    guards.push(pred.lock.lock());
+            Some(EntryRef::new(&*entry, &*old_value, guard))
+        }
+    }
+
+    /// Inserts `key` with `value`, and returns the entry whose value it
+    /// replaced with that value, which stays readable until `guard` is
+    /// dropped. The caller is left to reclaim the old value.
+    fn replace<'g>(&self, key: K, value: V, guard: &'g Guard) -> Option<Found<'g, K, V, C>> {
+        let top_level = self.level_generator.random_level(self.p, self.max_level);
+        self.raise_height(top_level + 1);
+        let mut preds = vec![null_mut(); self.max_level];
+        let mut succs = vec![null_mut(); self.max_level];
 
-                    valid = !pred.marked.load(Relaxed)
-                        && !(*succ).marked.load(Relaxed)
-                        && pred.nexts[level].load(Relaxed) == succ;
+        let (mut key, mut value) = (key, value);
 
-                    if !valid {
-                        break;
-                    }
+        loop {
+            let Some(level_found) = self.find(&key, &mut preds, &mut succs) else {
+                match self.link(key, value, top_level, &preds, &succs, guard) {
+                    Ok(_) => return None,
+                    Err((k, v)) => (key, value) = (k, v),
                 }
+
+                continue;
+            };
+
+            let entry_found = unsafe { &*succs[level_found] };
+
+            // Wait for an entry being added to be linked, and for one being
+            // removed to be unlinked.
+            if entry_found.marked.load(Acquire) || !entry_found.fully_linked.load(Acquire) {
+                spin_loop();
+                continue;
             }
 
-            if !valid {
-                // I suppose guards will be dropped here
+            // `remove` marks the entry under the same lock before it takes
+            // the value, so the entry cannot lose the new value to it unseen.
+            let _lock_guard = entry_found.lock.lock();
+
+            if entry_found.marked.load(Acquire) {
                 continue;
             }
 
-            let new_entry = Box::into_raw(Box::new(Entry {
-                key,
-                value: Some(value),
-                lock: ReentrantMutex::new(()),
-                marked: AtomicBool::new(false),
-                fully_linked: AtomicBool::new(false),
-                top_level,
-                nexts: (0..=top_level).map(|_| AtomicPtr::default()).collect(),
-            }));
+            let old_value = entry_found.value().swap(Owned::new(value), AcqRel, guard);
+
+            return Some((succs[level_found], old_value));
+        }
+    }
+
+    /// Returns the value of `key`, inserting the result of `f` first if the
+    /// key is absent. Check and insert are one atomic step: of several
+    /// threads racing on the same key, exactly one inserts and all of them
+    /// get its value.
+    ///
+    /// `f` is called at most once, and only once the key has been seen
+    /// absent. Its result is dropped if another thread inserts the key first.
+    ///
+    /// The value is handed out behind a reference, like [`get`](Self::get),
+    /// so it stays readable if the key is removed or replaced meanwhile.
+    pub fn get_or_insert_with<F>(&self, key: K, f: F) -> EntryRef<'_, K, V>
+    where
+        F: FnOnce() -> V,
+    {
+        let guard = epoch::pin();
+        let (Ok((entry, value)) | Err((entry, value))) = self.insert_with(key, f, &guard);
+        let value = value.as_raw();
+
+        unsafe { EntryRef::new(&*entry, &*value, guard) }
+    }
+
+    /// Inserts `key` with the value made by `f` unless it is present.
+    ///
+    /// Returns the new entry and value, or the entry that was already there
+    /// with the value it held. Both stay readable until `guard` is dropped.
+    fn insert_with<'g, F>(
+        &self,
+        key: K,
+        f: F,
+        guard: &'g Guard,
+    ) -> Result<Found<'g, K, V, C>, Found<'g, K, V, C>>
+    where
+        F: FnOnce() -> V,
+    {
+        let top_level = self.level_generator.random_level(self.p, self.max_level);
+        self.raise_height(top_level + 1);
+        let mut preds = vec![null_mut(); self.max_level];
+        let mut succs = vec![null_mut(); self.max_level];
+
+        let mut key = key;
+        let mut f = Some(f);
+        let mut value = None;
+
+        loop {
+            if let Some(present) = self.search(&key, &mut preds, &mut succs, guard) {
+                return Err(present);
+            }
+
+            // Made before taking any lock, and kept across retries.
+            if let Some(f) = f.take() {
+                value = Some(f());
+            }
+
+            match self.link(key, value.take().unwrap(), top_level, &preds, &succs, guard) {
+                Ok(new) => return Ok(new),
+                Err((k, v)) => (key, value) = (k, Some(v)),
+            }
+        }
+    }
 
+    /// Searches for `key` like `find`, waiting for an entry that is still
+    /// being added. Returns the entry holding `key` and its value, or `None`
+    /// with `preds` and `succs` ready for [`link`](Self::link) if it is
+    /// absent.
+    fn search<'g>(
+        &self,
+        key: &K,
+        preds: &mut [*mut Entry<K, V, C>],
+        succs: &mut [*mut Entry<K, V, C>],
+        guard: &'g Guard,
+    ) -> Option<Found<'g, K, V, C>> {
+        loop {
+            let level_found = self.find(key, preds, succs)?;
+
+            let entry_found = unsafe { &*succs[level_found] };
+            if !entry_found.marked.load(Acquire) {
+                while !entry_found.fully_linked.load(Acquire) {
+                    spin_loop();
+                }
+
+                // Gone if the entry has been removed since it was checked;
+                // the search will no longer find it.
+                let value = entry_found.value().load(Acquire, guard);
+
+                if !value.is_null() {
+                    return Some((succs[level_found], value));
+                }
+            }
+        }
+    }
+
+    /// Links a new entry between the `preds` and `succs` found by a search
+    /// for `key`, unless they changed since. Returns the new entry and value,
+    /// or the key and value to retry with.
+    ///
+    /// The value is returned as it was published rather than read back from
+    /// the entry, which a concurrent `remove` may empty as soon as the locks
+    /// are released. Like the entry, it stays readable until `guard` is
+    /// dropped.
+    fn link<'g>(
+        &self,
+        key: K,
+        value: V,
+        top_level: usize,
+        preds: &[*mut Entry<K, V, C>],
+        succs: &[*mut Entry<K, V, C>],
+        guard: &'g Guard,
+    ) -> Result<Found<'g, K, V, C>, (K, V)> {
+        let mut valid = true;
+        let mut guards = Vec::with_capacity(top_level + 1);
+
+        for level in 0..=top_level {
             unsafe {
-                for level in 0..=top_level {
-                    (*new_entry).nexts[level].store(succs[level], Relaxed);
-                    (*preds[level]).nexts[level].store(new_entry, Relaxed);
+                let pred = &mut *preds[level];
+                let succ = &mut *succs[level];
+
+                guards.push(pred.lock.lock());
+
+                valid = !pred.marked.load(Acquire)
+                    && !succ.marked.load(Acquire)
+                    && pred.nexts()[level].load(Acquire) == succ;
+
+                if !valid {
+                    break;
                 }
+            }
+        }
+
+        if !valid {
+            // I suppose guards will be dropped here
+            return Err((key, value));
+        }
+
+        let new_entry = Entry::alloc(Key::Entry(key), None, top_level);
+        let new_value = Owned::new(value).into_shared(guard);
+
+        // Readers take no locks, so each link is published with a release
+        // store, after the entry it points to has been filled in.
+        unsafe {
+            (*new_entry).value().store(new_value, Relaxed);
 
-                (*new_entry).fully_linked.store(true, Relaxed);
+            for level in 0..=top_level {
+                (*new_entry).nexts()[level].store(succs[level], Relaxed);
+                (*preds[level]).nexts()[level].store(new_entry, Release);
             }
 
-            return true;
+            (*new_entry).fully_linked.store(true, Release);
+        }
+
+        self.len.increment();
+
+        Ok((new_entry, new_value))
+    }
+
+    /// Removes `key` and returns a reference to its entry, which stays
+    /// readable until the reference is dropped.
+    pub fn remove<Q>(&self, key: &Q) -> Option<EntryRef<'_, K, V>>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        let guard = epoch::pin();
+        let (entry, value) = self.unlink(key, &guard)?;
+
+        unsafe {
+            guard.defer_destroy(value);
+            let value = value.as_raw();
+
+            Some(EntryRef::new(&*entry, &*value, guard))
         }
     }
 
-    pub fn remove(&self, key: K) -> Option<V> {
-        // let mut victim = None;
-        let mut is_marked = false;
-        let mut top_level = usize::MAX;
+    /// Removes the entry holding `key` and retires it, handing its value to
+    /// the caller. The entry is not reclaimed before `guard` is dropped.
+    fn unlink<'g, Q>(&self, key: &Q, guard: &'g Guard) -> Option<Found<'g, K, V, C>>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        let mut victim = null_mut();
+        let mut victim_guard = None;
+        let mut top_level = 0;
 
-        let mut preds = vec![null_mut(); MAX_LEVEL + 1];
-        let mut succs = vec![null_mut(); MAX_LEVEL + 1];
+        let mut preds = vec![null_mut(); self.max_level];
+        let mut succs = vec![null_mut(); self.max_level];
 
-        let key = Key::Entry(key);
         loop {
-            let level_found = self.find(&key, &mut preds, &mut succs);
+            let level_found = self.find(key, &mut preds, &mut succs);
 
-            let mut _victim_handle = None;
+            if victim_guard.is_none() {
+                let level_found = level_found?;
+                let candidate = unsafe { &*succs[level_found] };
 
-            if let Some(level_found) = level_found {
-                let victim = unsafe { &mut *succs[level_found] };
-
-                if is_marked
-                    || (victim.fully_linked.load(Relaxed)
-                        && victim.top_level == level_found
-                        && !victim.marked.load(Relaxed))
-                {
-                    if !is_marked {
-                        top_level = victim.top_level;
-                        _victim_handle = Some(&victim.lock.lock());
-                        if victim.marked.load(Relaxed) {
-                            return None;
-                        }
-
-                        victim.marked.store(true, Relaxed);
-                        is_marked = true;
-                    }
+                if !candidate.fully_linked.load(Acquire) || candidate.top_level != level_found {
+                    return None;
                 }
 
-                let mut highest_locked = usize::MAX;
+                let lock_guard = candidate.lock.lock();
+
+                // Already being removed by another thread.
+                if candidate.marked.load(Acquire) {
+                    return None;
+                }
 
-                let mut valid = true;
+                candidate.marked.store(true, Release);
+                top_level = candidate.top_level;
+                victim = succs[level_found];
+                victim_guard = Some(lock_guard);
+            }
 
-                let mut guards = Vec::with_capacity(top_level + 1);
+            let mut valid = true;
 
-                for level in 0..=top_level {
-                    unsafe {
-                        let pred = &mut *preds[level];
+            let mut guards = Vec::with_capacity(top_level + 1);
 
-                        guards.push(pred.lock.lock());
-                        if highest_locked < level {
-                            highest_locked = level;
-                        }
+            for (level, &pred) in preds[..=top_level].iter().enumerate() {
+                let pred = unsafe { &*pred };
 
-                        valid =
-                            !pred.marked.load(Relaxed) && pred.nexts[level].load(Relaxed) == (victim);
+                guards.push(pred.lock.lock());
 
-                        if !valid {
-                            break;
-                        }
-                    }
-                }
+                valid = !pred.marked.load(Acquire) && pred.nexts()[level].load(Acquire) == victim;
 
                 if !valid {
-                    continue;
+                    break;
+                }
+            }
+
+            if !valid {
+                continue;
+            }
+
+            unsafe {
+                for (level, &pred) in preds[..=top_level].iter().enumerate() {
+                    (*pred).nexts()[level].store((*victim).nexts()[level].load(Acquire), Release);
+                }
+            }
+
+            // Marked and unlinked, so no one else will take the value.
+            let value = unsafe { (*victim).value().swap(Shared::null(), AcqRel, guard) };
+
+            drop(guards);
+            drop(victim_guard);
+            self.len.decrement();
+            unsafe { self.retire(victim, guard) };
+
+            return Some((victim, value));
+        }
+    }
+
+    /// Hands an entry unlinked from every level over to the collector, which
+    /// frees it once every thread that may still be reading it has unpinned.
+    unsafe fn retire(&self, entry: *mut Entry<K, V, C>, guard: &Guard) {
+        guard.defer_unchecked(move || Entry::free(entry));
+    }
+
+    /// Returns an iterator over the entries whose keys lie in `range`, in
+    /// order. The start is located by a search from the head, so only the
+    /// entries in the range are visited. See [`Range`] for how it behaves
+    /// under concurrent changes.
+    pub fn range<Q, R>(&self, range: R) -> Range<'_, Q, R, K, V, C>
+    where
+        Q: ?Sized,
+        R: RangeBounds<Q>,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        let guard = epoch::pin();
+
+        let start = match range.start_bound() {
+            Bound::Unbounded => unsafe { (*self.head).nexts()[0].load(Acquire) },
+            Bound::Included(key) | Bound::Excluded(key) => {
+                let mut preds = vec![null_mut(); self.max_level];
+                let mut succs = vec![null_mut(); self.max_level];
+
+                let found = self.find(key, &mut preds, &mut succs).is_some();
+
+                if found && matches!(range.start_bound(), Bound::Excluded(_)) {
+                    unsafe { (*succs[0]).nexts()[0].load(Acquire) }
+                } else {
+                    succs[0]
                 }
+            }
+        };
+
+        Range::new(self, start, range, guard)
+    }
 
-                unsafe {
-                    for level in 0..=top_level {
-                        (*preds[level]).nexts[level]
-                            .store((*succs[level]).nexts[level].load(Relaxed), Relaxed);
-                    }
+    /// Returns the entry with the largest key less than or equal to `key`.
+    pub fn floor<Q>(&self, key: &Q) -> Option<EntryRef<'_, K, V>>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        let guard = epoch::pin();
+        let (entry, value) =
+            self.last_where(|current| current.cmp_by(key, &self.key_cmp).is_le(), &guard)?;
+        let value = value.as_raw();
+
+        unsafe { Some(EntryRef::new(&*entry, &*value, guard)) }
+    }
+
+    /// Returns the entry with the smallest key greater than or equal to
+    /// `key`.
+    pub fn ceiling<Q>(&self, key: &Q) -> Option<EntryRef<'_, K, V>>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        let guard = epoch::pin();
+        let mut preds = vec![null_mut(); self.max_level];
+        let mut succs = vec![null_mut(); self.max_level];
+
+        self.find(key, &mut preds, &mut succs);
+
+        let (entry, value) = self.first_from(succs[0], |_| false, &guard)?;
+        let value = value.as_raw();
+
+        unsafe { Some(EntryRef::new(&*entry, &*value, guard)) }
+    }
+
+    /// Returns the entry with the largest key strictly less than `key`.
+    pub fn lower<Q>(&self, key: &Q) -> Option<EntryRef<'_, K, V>>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        let guard = epoch::pin();
+        let (entry, value) =
+            self.last_where(|current| current.cmp_by(key, &self.key_cmp).is_lt(), &guard)?;
+        let value = value.as_raw();
+
+        unsafe { Some(EntryRef::new(&*entry, &*value, guard)) }
+    }
+
+    /// Returns the entry with the smallest key strictly greater than `key`.
+    pub fn higher<Q>(&self, key: &Q) -> Option<EntryRef<'_, K, V>>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        let guard = epoch::pin();
+        let mut preds = vec![null_mut(); self.max_level];
+        let mut succs = vec![null_mut(); self.max_level];
+
+        self.find(key, &mut preds, &mut succs);
+
+        let (entry, value) = self.first_from(
+            succs[0],
+            |current| current.cmp_by(key, &self.key_cmp).is_eq(),
+            &guard,
+        )?;
+        let value = value.as_raw();
+
+        unsafe { Some(EntryRef::new(&*entry, &*value, guard)) }
+    }
+
+    /// Returns the entry with the first key not less than `key`, as C++'s
+    /// `lower_bound` does; the same entry as [`ceiling`](Self::ceiling).
+    pub fn lower_bound<Q>(&self, key: &Q) -> Option<EntryRef<'_, K, V>>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        self.ceiling(key)
+    }
+
+    /// Returns the entry with the first key greater than `key`, as C++'s
+    /// `upper_bound` does; the same entry as [`higher`](Self::higher).
+    pub fn upper_bound<Q>(&self, key: &Q) -> Option<EntryRef<'_, K, V>>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        self.higher(key)
+    }
+
+    /// Returns the entry with the smallest key.
+    pub fn first(&self) -> Option<EntryRef<'_, K, V>> {
+        let guard = epoch::pin();
+        let first = unsafe { (*self.head).nexts()[0].load(Acquire) };
+        let (entry, value) = self.first_from(first, |_| false, &guard)?;
+        let value = value.as_raw();
+
+        unsafe { Some(EntryRef::new(&*entry, &*value, guard)) }
+    }
+
+    /// Returns the entry with the largest key.
+    pub fn last(&self) -> Option<EntryRef<'_, K, V>> {
+        let guard = epoch::pin();
+        let (entry, value) = self.last_where(|current| !matches!(current, Key::Tail), &guard)?;
+        let value = value.as_raw();
+
+        unsafe { Some(EntryRef::new(&*entry, &*value, guard)) }
+    }
+
+    /// Walks the bottom level from `current` to the first entry that is
+    /// present and not skipped, the same way [`Range`] does.
+    fn first_from<'g, F>(
+        &self,
+        mut current: *mut Entry<K, V, C>,
+        skip: F,
+        guard: &'g Guard,
+    ) -> Option<Found<'g, K, V, C>>
+    where
+        F: Fn(&Key<K>) -> bool,
+    {
+        loop {
+            // Not freed while `guard` is pinned, even if removed meanwhile.
+            let entry = unsafe { &*current };
+
+            if let Key::Tail = entry.key {
+                return None;
+            }
+
+            let next = entry.nexts()[0].load(Acquire);
+
+            if !entry.marked.load(Acquire) && entry.fully_linked.load(Acquire) && !skip(&entry.key)
+            {
+                let value = entry.value().load(Acquire, guard);
+
+                if !value.is_null() {
+                    return Some((current, value));
+                }
+            }
+
+            current = next;
+        }
+    }
+
+    /// Returns the last entry whose key is `before` the target.
+    ///
+    /// Descends like `find`, following links while `before` holds. An entry
+    /// found halfway through being added or removed cannot be stepped back
+    /// from, so the descent starts over until it settles.
+    fn last_where<'g, F>(&self, before: F, guard: &'g Guard) -> Option<Found<'g, K, V, C>>
+    where
+        F: Fn(&Key<K>) -> bool,
+    {
+        loop {
+            let mut pred = self.head;
+
+            for level in (0..self.current_height.load(Relaxed)).rev() {
+                let mut current = unsafe { (*pred).nexts()[level].load(Acquire) };
+
+                while before(unsafe { &(*current).key }) {
+                    pred = current;
+                    current = unsafe { (*current).nexts()[level].load(Acquire) };
                 }
+            }
+
+            let entry = unsafe { &*pred };
 
-                return victim.value.take();
-            } else {
+            if let Key::Head = entry.key {
                 return None;
             }
+
+            if entry.marked.load(Acquire) || !entry.fully_linked.load(Acquire) {
+                spin_loop();
+                continue;
+            }
+
+            let value = entry.value().load(Acquire, guard);
+
+            if !value.is_null() {
+                return Some((pred, value));
+            }
+        }
+    }
+
+    /// Returns the number of entries.
+    ///
+    /// Every successful add and remove updates a counter split per thread.
+    /// The count is exact while no add or remove is running; while they are,
+    /// it may include some of them and not others, so it is only an
+    /// approximation.
+    pub fn len(&self) -> usize {
+        self.len.sum()
+    }
+
+    /// Same as `len() == 0`, with the same caveat under concurrent writes.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Raises the current height to at least `height`. A later read of it
+    /// by the same thread sees no less.
+    fn raise_height(&self, height: usize) {
+        if self.current_height.load(Relaxed) < height {
+            self.current_height.fetch_max(height, Relaxed);
         }
     }
 
-    fn find<'a, 'b>(
+    /// Searches for `key` from the current height down, filling `preds` and
+    /// `succs` on the levels searched.
+    ///
+    /// The height read may be stale, which only leaves express levels
+    /// unused, except that callers take the levels of the entry found as
+    /// searched. If it reaches above them, the height is raised past it and
+    /// the search starts over. An inserter raises the height before its
+    /// search for the same reason.
+    fn find<'a, 'b, Q>(
         &'a self,
-        key: &Key<K>,
-        preds: &'b mut Vec<*mut Entry<K, V, C>>,
-        succs: &'b mut Vec<*mut Entry<K, V, C>>,
+        key: &Q,
+        preds: &'b mut [*mut Entry<K, V, C>],
+        succs: &'b mut [*mut Entry<K, V, C>],
     ) -> Option<usize>
     where
         'a: 'b,
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
     {
         let head = self.head;
 
-        let mut level_found = None;
+        loop {
+            let height = self.current_height.load(Relaxed);
+
+            let mut level_found = None;
 
-        let mut pred = unsafe { &mut *head };
+            let mut pred = unsafe { &mut *head };
 
-        for level in (0..MAX_LEVEL).rev() {
-            let mut current = unsafe { pred.nexts[level].load(Relaxed).as_mut().unwrap() };
+            for level in (0..height).rev() {
+                let mut current = unsafe { pred.nexts()[level].load(Acquire).as_mut().unwrap() };
 
-            while current.key < *key {
-                pred = current;
-                current = unsafe { pred.nexts[level].load(Relaxed).as_mut().unwrap() };
+                while current.key.cmp_by(key, &self.key_cmp).is_lt() {
+                    pred = current;
+                    current = unsafe { pred.nexts()[level].load(Acquire).as_mut().unwrap() };
+                }
+
+                if level_found.is_none() && current.key.cmp_by(key, &self.key_cmp).is_eq() {
+                    level_found = Some(level);
+                }
+
+                preds[level] = pred;
+                succs[level] = current;
             }
 
-            if level_found.is_none() && current.key == *key {
-                level_found = Some(level);
+            if let Some(level_found) = level_found {
+                let top_level = unsafe { (*succs[level_found]).top_level };
+
+                if top_level >= height {
+                    self.raise_height(top_level + 1);
+                    continue;
+                }
             }
 
-            preds[level] = pred;
-            succs[level] = current;
+            return level_found;
         }
-
-        return level_found;
     }
 
-    pub fn get(&self, key: K) -> Option<&V> {
-        let mut pred = self.head;
-
-        let key = Key::Entry(key);
+    /// Looks up `key` without taking any lock.
+    pub fn get<Q>(&self, key: &Q) -> Option<EntryRef<'_, K, V>>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        let guard = epoch::pin();
+        let mut pred = unsafe { &*self.head };
 
-        for level in (0..MAX_LEVEL).rev() {
-            let mut current = unsafe { (*pred).nexts[level].load(Relaxed).as_mut().unwrap() };
+        for level in (0..self.current_height.load(Relaxed)).rev() {
+            let mut current = unsafe { &*pred.nexts()[level].load(Acquire) };
 
-            while current.key < key {
+            while current.key.cmp_by(key, &self.key_cmp).is_lt() {
                 pred = current;
-                current = unsafe { (*current).nexts[level].load(Relaxed).as_mut().unwrap() };
+                current = unsafe { &*current.nexts()[level].load(Acquire) };
             }
 
-            if current.key == key {
-                return current.value.as_ref();
+            if current.key.cmp_by(key, &self.key_cmp).is_eq() {
+                // A marked entry is being removed, and its value is only
+                // taken once it is marked.
+                if !current.fully_linked.load(Acquire) || current.marked.load(Acquire) {
+                    return None;
+                }
+
+                let value = current.value().load(Acquire, &guard).as_raw();
+
+                if value.is_null() {
+                    return None;
+                }
+
+                return Some(EntryRef::new(current, unsafe { &*value }, guard));
             }
         }
 
-        return None;
+        None
+    }
+}
+
+impl<K, V, C> Drop for SkipList<K, V, C>
+where
+    C: Comparator<K>,
+{
+    fn drop(&mut self) {
+        // Removed entries are already unlinked from every level and left to
+        // the collector, so the bottom level holds exactly the entries we
+        // still own, head and tail included.
+        let mut current = self.head;
+
+        while !current.is_null() {
+            let next = unsafe { (*current).nexts()[0].load(Relaxed) };
+            unsafe { Entry::free(current) };
+            current = next;
+        }
+    }
+}
+
+impl<K, V, C> OrderedMap<K, V> for SkipList<K, V, C>
+where
+    K: Send + 'static,
+    V: Send + 'static,
+    C: Comparator<K>,
+{
+    type Ref<'a>
+        = EntryRef<'a, K, V>
+    where
+        Self: 'a;
+
+    fn add(&mut self, key: K, value: V) -> bool {
+        SkipList::add(self, key, value)
+    }
+
+    fn try_insert(&mut self, key: K, value: V) -> Result<(), (K, V)> {
+        SkipList::try_insert(self, key, value)
+    }
+
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        // `&mut self` rules out readers, so nothing needs to wait for them:
+        // the old value is moved out right away.
+        let guard = unsafe { epoch::unprotected() };
+        let (_, old_value) = self.replace(key, value, guard)?;
+
+        Some(*unsafe { old_value.into_owned() }.into_box())
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        // `&mut self` rules out readers, so nothing needs to wait for them:
+        // the entry is freed and the value moved out right away.
+        let guard = unsafe { epoch::unprotected() };
+        let (_, value) = self.unlink(key, guard)?;
+
+        Some(*unsafe { value.into_owned() }.into_box())
+    }
+
+    fn get(&self, key: &K) -> Option<EntryRef<'_, K, V>> {
+        SkipList::get(self, key)
+    }
+
+    fn len(&self) -> usize {
+        SkipList::len(self)
     }
 }
 
-static P: f32 = 0.5;
-static MAX_LEVEL: usize = 32;
+impl<K, V, C> ConcurrentOrderedMap<K, V> for SkipList<K, V, C>
+where
+    K: Send + Sync + 'static,
+    V: Send + Sync + 'static,
+    C: Comparator<K> + Sync,
+{
+    type Removed<'a>
+        = EntryRef<'a, K, V>
+    where
+        Self: 'a;
+
+    fn add_shared(&self, key: K, value: V) -> bool {
+        SkipList::add(self, key, value)
+    }
+
+    fn insert_shared(&self, key: K, value: V) -> Option<EntryRef<'_, K, V>> {
+        SkipList::insert(self, key, value)
+    }
 
-fn random_level() -> usize {
-    let level = (f32::log2(1. - random::<f32>()) / f32::log2(1. - P)) as usize;
-    return min(level, MAX_LEVEL);
+    fn remove_shared(&self, key: &K) -> Option<EntryRef<'_, K, V>> {
+        SkipList::remove(self, key)
+    }
 }
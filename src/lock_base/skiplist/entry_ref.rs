@@ -0,0 +1,60 @@
+use std::borrow::Borrow;
+use std::ops::Deref;
+
+use crossbeam_epoch::Guard;
+
+use super::entry::{Entry, Key};
+use crate::Comparator;
+
+/// A reference to an entry of a lock-based [`SkipList`](super::SkipList).
+///
+/// Locks only keep writers apart; readers take none, so a removed entry is
+/// reclaimed once no thread is pinned to it. The reference keeps the
+/// current thread pinned, which delays reclamation of every entry removed
+/// since, so drop it once done.
+pub struct EntryRef<'a, K, V> {
+    key: &'a K,
+    value: &'a V,
+    _guard: Guard,
+}
+
+impl<'a, K, V> EntryRef<'a, K, V> {
+    /// `value` is the value `entry` held, or still holds, while `guard` was
+    /// pinned.
+    pub(super) fn new<C>(entry: &'a Entry<K, V, C>, value: &'a V, guard: Guard) -> Self
+    where
+        C: Comparator<K>,
+    {
+        let Key::Entry(key) = &entry.key else {
+            unreachable!("head and tail are never handed out")
+        };
+
+        EntryRef {
+            key,
+            value,
+            _guard: guard,
+        }
+    }
+
+    pub fn key(&self) -> &K {
+        self.key
+    }
+
+    pub fn value(&self) -> &V {
+        self.value
+    }
+}
+
+impl<K, V> Deref for EntryRef<'_, K, V> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        self.value
+    }
+}
+
+impl<K, V> Borrow<V> for EntryRef<'_, K, V> {
+    fn borrow(&self) -> &V {
+        self.value
+    }
+}
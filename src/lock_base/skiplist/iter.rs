@@ -0,0 +1,106 @@
+use std::borrow::Borrow;
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::ops::RangeBounds;
+use std::sync::atomic::Ordering::*;
+
+use crossbeam_epoch::{self as epoch, Guard};
+
+use super::entry::{Entry, Key};
+use super::{EntryRef, SkipList};
+use crate::{past_end, Comparator};
+
+/// An iterator over the entries of a [`SkipList`] whose keys lie in a range,
+/// in key order. Created by [`SkipList::range`].
+///
+/// Entries are read one at a time along the bottom level while other threads
+/// may be writing, so the iterator skips entries that are being added or
+/// removed at the moment they are reached, yields whichever value an entry
+/// holds when it is reached, and may or may not see changes made behind or
+/// ahead of it.
+///
+/// The iterator keeps the current thread pinned, which delays reclamation
+/// of removed entries until it is dropped.
+pub struct Range<'a, Q, R, K, V, C>
+where
+    Q: ?Sized,
+    C: Comparator<K>,
+{
+    list: &'a SkipList<K, V, C>,
+    // Next entry to look at.
+    current: *mut Entry<K, V, C>,
+    range: R,
+    // Keeps `current` alive after it is removed, so that its links can
+    // still be followed.
+    guard: Guard,
+    _marker: PhantomData<fn(&Q)>,
+}
+
+impl<'a, Q, R, K, V, C> Range<'a, Q, R, K, V, C>
+where
+    Q: ?Sized,
+    C: Comparator<K>,
+{
+    /// `guard` must have been pinned before `current` was read.
+    pub(super) fn new(
+        list: &'a SkipList<K, V, C>,
+        current: *mut Entry<K, V, C>,
+        range: R,
+        guard: Guard,
+    ) -> Self {
+        Range {
+            list,
+            current,
+            range,
+            guard,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, Q, R, K, V, C> Iterator for Range<'a, Q, R, K, V, C>
+where
+    Q: ?Sized,
+    R: RangeBounds<Q>,
+    K: Borrow<Q>,
+    C: Comparator<K> + Comparator<Q>,
+{
+    type Item = EntryRef<'a, K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let entry = unsafe { &*self.current };
+
+            let Key::Entry(key) = &entry.key else {
+                return None;
+            };
+
+            if past_end(self.range.end_bound(), |end| {
+                self.list.key_cmp.compare(key.borrow(), end)
+            }) {
+                return None;
+            }
+
+            self.current = entry.nexts()[0].load(Acquire);
+
+            if entry.marked.load(Acquire) || !entry.fully_linked.load(Acquire) {
+                continue;
+            }
+
+            let value = entry.value().load(Acquire, &self.guard).as_raw();
+
+            if !value.is_null() {
+                return Some(EntryRef::new(entry, unsafe { &*value }, epoch::pin()));
+            }
+        }
+    }
+}
+
+impl<Q, R, K, V, C> FusedIterator for Range<'_, Q, R, K, V, C>
+where
+    Q: ?Sized,
+    R: RangeBounds<Q>,
+    K: Borrow<Q>,
+    C: Comparator<K> + Comparator<Q>,
+{
+}
@@ -0,0 +1,166 @@
+use std::borrow::Borrow;
+use std::iter::FusedIterator;
+use std::ops::{RangeBounds, RangeFull};
+
+use super::skiplist::{EntryRef, Range, SkipList};
+use crate::{Comparator, OrdComparator};
+
+/// A concurrent ordered set, kept as a [`SkipList`] whose values are all
+/// `()`.
+///
+/// Iterating yields [`EntryRef`]s rather than plain references, since a key
+/// another thread removes is only kept alive while something refers to it;
+/// read the key with `key()`.
+pub struct SkipSet<K, C = OrdComparator>
+where
+    C: Comparator<K>,
+{
+    list: SkipList<K, (), C>,
+}
+
+impl<K> SkipSet<K>
+where
+    K: Ord,
+{
+    pub fn new() -> Self {
+        SkipSet::with_cmp(OrdComparator)
+    }
+}
+
+impl<K> Default for SkipSet<K>
+where
+    K: Ord,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, C> SkipSet<K, C>
+where
+    C: Comparator<K>,
+{
+    pub fn with_cmp(cmp: C) -> Self {
+        SkipSet {
+            list: SkipList::with_cmp(cmp),
+        }
+    }
+}
+
+impl<K, C> SkipSet<K, C>
+where
+    K: Send + 'static,
+    C: Comparator<K>,
+{
+    /// Adds `key` to the set. Returns whether it was absent.
+    pub fn insert(&self, key: K) -> bool {
+        self.list.add(key, ())
+    }
+
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        self.list.get(key).is_some()
+    }
+
+    /// Removes `key` from the set. Returns whether it was present.
+    pub fn remove<Q>(&self, key: &Q) -> bool
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        self.list.remove(key).is_some()
+    }
+
+    /// Returns the number of keys, with the same caveat under concurrent
+    /// writes as [`SkipList::len`].
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    /// Returns an iterator over the keys, in order, that observes concurrent
+    /// changes the same way [`Range`] does.
+    pub fn iter(&self) -> SetIter<'_, K, C> {
+        SetIter(self.list.range::<K, _>(..))
+    }
+
+    /// Returns an iterator over the keys that lie in `range`, in order.
+    pub fn range<Q, R>(&self, range: R) -> SetRange<'_, Q, R, K, C>
+    where
+        Q: ?Sized,
+        R: RangeBounds<Q>,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        SetRange(self.list.range(range))
+    }
+}
+
+impl<'a, K, C> IntoIterator for &'a SkipSet<K, C>
+where
+    K: Send + 'static,
+    C: Comparator<K>,
+{
+    type Item = EntryRef<'a, K, ()>;
+    type IntoIter = SetIter<'a, K, C>;
+
+    fn into_iter(self) -> SetIter<'a, K, C> {
+        self.iter()
+    }
+}
+
+/// An iterator over the keys of a [`SkipSet`], in order.
+pub struct SetIter<'a, K, C>(Range<'a, K, RangeFull, K, (), C>)
+where
+    C: Comparator<K>;
+
+impl<'a, K, C> Iterator for SetIter<'a, K, C>
+where
+    C: Comparator<K>,
+{
+    type Item = EntryRef<'a, K, ()>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+impl<K, C> FusedIterator for SetIter<'_, K, C> where C: Comparator<K> {}
+
+/// An iterator over the keys of a [`SkipSet`] that lie in a range, in order.
+/// Created by [`SkipSet::range`].
+pub struct SetRange<'a, Q, R, K, C>(Range<'a, Q, R, K, (), C>)
+where
+    Q: ?Sized,
+    C: Comparator<K>;
+
+impl<'a, Q, R, K, C> Iterator for SetRange<'a, Q, R, K, C>
+where
+    Q: ?Sized,
+    R: RangeBounds<Q>,
+    K: Borrow<Q>,
+    C: Comparator<K> + Comparator<Q>,
+{
+    type Item = EntryRef<'a, K, ()>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+impl<Q, R, K, C> FusedIterator for SetRange<'_, Q, R, K, C>
+where
+    Q: ?Sized,
+    R: RangeBounds<Q>,
+    K: Borrow<Q>,
+    C: Comparator<K> + Comparator<Q>,
+{
+}
@@ -0,0 +1,171 @@
+use std::thread;
+
+use super::*;
+use crate::ordered_map::tests::{
+    test_concurrent_get_or_insert, test_concurrent_insert, test_concurrent_ordered_map,
+    test_ordered_map, test_ordered_map_config, test_ordered_map_drop, test_ordered_map_navigate,
+    test_ordered_map_range, test_ordered_map_unit_drop, test_ordered_map_with_cmp, Navigate,
+};
+
+#[test]
+fn test_lockbase_ordered_map() {
+    test_ordered_map(SkipList::new());
+}
+
+#[test]
+fn test_lockbase_ordered_map_drop() {
+    test_ordered_map_drop(SkipList::new);
+}
+
+#[test]
+fn test_lockbase_ordered_map_unit_drop() {
+    test_ordered_map_unit_drop(SkipList::new);
+}
+
+#[test]
+fn test_lockbase_ordered_map_with_cmp() {
+    test_ordered_map_with_cmp(SkipList::with_cmp);
+}
+
+#[test]
+fn test_lockbase_ordered_map_config() {
+    test_ordered_map_config(SkipList::with_config);
+}
+
+#[test]
+fn test_lockbase_ordered_map_range() {
+    test_ordered_map_range(SkipList::new(), |map, range| {
+        map.range(range).map(|entry| *entry.key()).collect()
+    });
+}
+
+#[test]
+fn test_lockbase_ordered_map_navigate() {
+    test_ordered_map_navigate(SkipList::new(), |map, query| {
+        let entry = match query {
+            Navigate::Floor(key) => map.floor(&key),
+            Navigate::Ceiling(key) => map.ceiling(&key),
+            Navigate::Lower(key) => map.lower(&key),
+            Navigate::Higher(key) => map.higher(&key),
+            Navigate::LowerBound(key) => map.lower_bound(&key),
+            Navigate::UpperBound(key) => map.upper_bound(&key),
+            Navigate::First => map.first(),
+            Navigate::Last => map.last(),
+        };
+
+        entry.map(|entry| (*entry.key(), *entry))
+    });
+}
+
+#[test]
+fn test_lockbase_insert_keeps_key() {
+    let list =
+        SkipList::with_cmp(|k1: &String, k2: &String| k1.to_lowercase().cmp(&k2.to_lowercase()));
+
+    assert!(list.insert("Apple".to_string(), 1).is_none());
+    assert_eq!(list.insert("APPLE".to_string(), 2).as_deref(), Some(&1));
+
+    let entry = list.get(&"apple".to_string()).unwrap();
+    assert_eq!(entry.key(), "Apple");
+    assert_eq!(*entry, 2);
+    assert_eq!(list.len(), 1);
+}
+
+#[test]
+fn test_lockbase_borrowed_keys() {
+    let strings = SkipList::new();
+    assert!(strings.add("apple".to_string(), 1));
+    assert_eq!(strings.get("apple").as_deref(), Some(&1));
+    assert_eq!(strings.remove("apple").as_deref(), Some(&1));
+    assert!(strings.get("apple").is_none());
+}
+
+#[test]
+fn test_lockbase_concurrent_ordered_map() {
+    test_concurrent_ordered_map(&SkipList::new());
+}
+
+#[test]
+fn test_lockbase_concurrent_insert() {
+    test_concurrent_insert(&SkipList::new());
+}
+
+#[test]
+fn test_lockbase_concurrent_get_or_insert() {
+    test_concurrent_get_or_insert(&SkipList::new(), |map, key, f| {
+        *map.get_or_insert_with(key, f)
+    });
+}
+
+#[test]
+fn test_lockbase_get_or_insert_outlives_remove() {
+    let list = SkipList::new();
+
+    let entry = list.get_or_insert_with(1, || "one".to_string());
+    assert_eq!(list.remove(&1).as_deref().map(String::as_str), Some("one"));
+    assert_eq!((*entry.key(), entry.as_str()), (1, "one"));
+    assert!(list.is_empty());
+}
+
+#[test]
+fn test_lockbase_get_or_insert_against_remove() {
+    let list = SkipList::new();
+
+    // Half the threads insert each key, the other half remove it, so values
+    // are often removed right after being linked.
+    thread::scope(|s| {
+        for t in 0..8 {
+            let list = &list;
+
+            s.spawn(move || {
+                for _ in 0..1000 {
+                    for key in 0..64 {
+                        if t % 2 == 0 {
+                            let entry = list.get_or_insert_with(key, || key.to_string());
+                            assert_eq!(*entry, key.to_string());
+                        } else if let Some(entry) = list.remove(&key) {
+                            assert_eq!(*entry, key.to_string());
+                        }
+                    }
+                }
+            });
+        }
+    });
+}
+
+#[test]
+fn test_lockbase_skipset() {
+    let set = SkipSet::new();
+
+    // Each thread adds its own residue class, then drops every other key.
+    thread::scope(|s| {
+        for t in 0..4 {
+            let set = &set;
+
+            s.spawn(move || {
+                for i in (t..1000).step_by(4) {
+                    assert!(set.insert(i));
+                }
+
+                for i in (t..1000).step_by(8) {
+                    assert!(set.remove(&i));
+                }
+            });
+        }
+    });
+
+    let kept = (0..1000).filter(|i| i % 8 >= 4).collect::<Vec<_>>();
+
+    assert_eq!(set.len(), kept.len());
+    assert!(!set.insert(4));
+    assert!(set.contains(&4));
+    assert!(!set.contains(&3));
+    assert!(set
+        .iter()
+        .map(|entry| *entry.key())
+        .eq(kept.iter().copied()));
+    assert!(set
+        .range(..20)
+        .map(|entry| *entry.key())
+        .eq([4, 5, 6, 7, 12, 13, 14, 15]));
+}
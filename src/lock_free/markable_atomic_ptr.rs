@@ -8,19 +8,13 @@ pub struct MarkableAtomicPtr<T> {
 const MASK: usize = !0b1;
 
 impl<T> MarkableAtomicPtr<T> {
-    pub fn new(ptr: *mut T, mark: bool) -> Self {
-        Self {
-            ptr: AtomicPtr::new(ptr.map_addr(|p| p | mark as usize)),
-        }
-    }
-
     pub fn load_ptr(&self, order: Ordering) -> *mut T {
-        self.ptr.load(order).mask(MASK)
+        self.ptr.load(order).map_addr(|p| p & MASK)
     }
 
     pub fn load(&self, order: Ordering) -> (*mut T, bool) {
         let ptr = self.ptr.load(order);
-        (ptr.mask(MASK), ptr.addr() & !MASK != 0)
+        (ptr.map_addr(|p| p & MASK), ptr.addr() & !MASK != 0)
     }
 
     pub fn store(&self, ptr: *mut T, mark: bool, order: Ordering) {
@@ -43,8 +37,8 @@ impl<T> MarkableAtomicPtr<T> {
                 succ_order,
                 fail_order,
             )
-            .map(|p| p.mask(MASK))
-            .map_err(|p| (p.mask(MASK), p.addr() & !MASK != 0))
+            .map(|p| p.map_addr(|p| p & MASK))
+            .map_err(|p| (p.map_addr(|p| p & MASK), p.addr() & !MASK != 0))
     }
 }
 
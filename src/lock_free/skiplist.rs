@@ -1,219 +1,875 @@
-use core::panic;
-use std::backtrace::Backtrace;
-use std::fmt::Debug;
-use std::intrinsics::breakpoint;
-use std::ptr::null;
-use std::sync::atomic::{AtomicBool, Ordering::*};
-use std::sync::{Mutex, MutexGuard};
-use std::{
-    cmp::{min, Ordering},
-    ptr::null_mut,
-    sync::atomic::AtomicPtr,
-};
+use std::ops::{Bound, RangeBounds};
+use std::sync::atomic::{AtomicUsize, Ordering::*};
+use std::{borrow::Borrow, ptr::null_mut};
 
 use self::entry::{Entry, Key};
-use parking_lot::ReentrantMutex;
-use rand::random;
+use crate::striped_counter::StripedCounter;
+use crate::{Comparator, ConcurrentOrderedMap, OrdComparator, OrderedMap};
+use crate::{LevelGenerator, SkipListConfig, MAX_LEVEL};
+use crossbeam_epoch::{self as epoch, Guard, Owned, Shared};
 
-mod entry;
-
-const MASK: usize = !0b1;
+pub use self::entry_ref::{CompareAndSwapError, EntryRef};
+pub use self::iter::{Iter, Range};
 
-fn extract_ptr<'a, T>(ptr: *mut T) -> (&'a mut T, bool) {
-    if ptr.is_null() {
-        panic!("ptr is null {}", Backtrace::force_capture());
-    }
+mod entry;
+mod entry_ref;
+mod iter;
 
-    unsafe { (ptr.mask(MASK).as_mut().unwrap(), ptr.addr() & !MASK != 0) }
-}
+/// An entry found in the list together with the value it held when found.
+type Found<'g, K, V, C> = (*mut Entry<K, V, C>, Shared<'g, V>);
 
-fn mark_ptr<T>(ptr: *mut T) -> *mut T {
-    (ptr as *mut T).map_addr(|p| p | !MASK)
-}
-
-pub struct SkipList<K, V, C = fn(&K, &K) -> Ordering>
+pub struct SkipList<K, V, C = OrdComparator>
 where
-    K: Ord,
-    C: Fn(&K, &K) -> Ordering,
+    C: Comparator<K>,
 {
     head: *mut Entry<K, V, C>,
     key_cmp: C,
-}
-
-fn default_cmp<K: Ord>(k1: &K, k2: &K) -> Ordering {
-    k1.cmp(k2)
+    len: StripedCounter,
+    p: f32,
+    level_generator: Box<dyn LevelGenerator + Send + Sync>,
+    /// How many levels the head has, which no entry exceeds. Searches keep
+    /// their preds and succs in arrays of [`MAX_LEVEL`], the most a list
+    /// can have.
+    max_level: usize,
+    /// How many levels, counted from the bottom, any entry has reached, so
+    /// that searches start there. Only ever raised, before an entry of that
+    /// height is searched for; see `find` for why a stale read is harmless.
+    current_height: AtomicUsize,
 }
 
 impl<K, V> SkipList<K, V>
 where
-    K: Ord + Debug,
+    K: Ord,
 {
     pub fn new() -> Self {
-        SkipList::with_cmp(default_cmp)
+        SkipList::with_cmp(OrdComparator)
+    }
+}
+
+impl<K, V> Default for SkipList<K, V>
+where
+    K: Ord,
+{
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 unsafe impl<K, V, C> Send for SkipList<K, V, C>
 where
-    K: Ord + Send,
+    K: Send,
     V: Send,
-    C: Fn(&K, &K) -> Ordering + Send,
+    C: Comparator<K> + Send,
 {
 }
 
 unsafe impl<K, V, C> Sync for SkipList<K, V, C>
 where
-    K: Ord + Sync,
-    V: Sync,
-    C: Fn(&K, &K) -> Ordering + Sync,
+    K: Send + Sync,
+    V: Send + Sync,
+    C: Comparator<K> + Sync,
 {
 }
 
 impl<K, V, C> SkipList<K, V, C>
 where
-    K: Ord + Debug,
-    C: Fn(&K, &K) -> Ordering,
+    C: Comparator<K>,
 {
     pub fn with_cmp(cmp: C) -> Self {
+        SkipList::with_config(SkipListConfig::new().cmp(cmp))
+    }
+
+    /// Creates a list with the promotion probability, number of levels,
+    /// comparator and level generator of `config`.
+    ///
+    /// # Panics
+    ///
+    /// If `config` holds a `p` or `max_level` out of bounds.
+    pub fn with_config<G>(config: SkipListConfig<C, G>) -> Self
+    where
+        G: LevelGenerator + Send + Sync + 'static,
+    {
+        config.validate();
+
+        let max_level = config.max_level;
+
         unsafe {
-            let head = &mut *Box::into_raw(Box::new(Entry::new(Key::Head, None, MAX_LEVEL)));
-            let tail = &mut *Box::into_raw(Box::new(Entry::new(Key::Tail, None, MAX_LEVEL)));
+            let head = &mut *Entry::alloc(Key::Head, None, max_level - 1);
+            let tail = &mut *Entry::alloc(Key::Tail, None, max_level - 1);
 
-            for level in 0..=MAX_LEVEL {
-                (*head).nexts[level].store(tail, false, Relaxed);
+            for level in 0..max_level {
+                head.nexts()[level].store(tail, false, Relaxed);
             }
 
-            SkipList { head, key_cmp: cmp }
+            SkipList {
+                head,
+                key_cmp: config.cmp,
+                len: StripedCounter::new(),
+                p: config.p,
+                level_generator: Box::new(config.level_generator),
+                max_level,
+                current_height: AtomicUsize::new(1),
+            }
         }
     }
+}
 
+// Removed entries may be dropped by whichever thread happens to collect them,
+// and only after this call has returned.
+impl<K, V, C> SkipList<K, V, C>
+where
+    K: Send + 'static,
+    V: Send + 'static,
+    C: Comparator<K>,
+{
     pub fn add(&self, key: K, value: V) -> bool {
-        let top_level = random_level();
-        let bottom_level = 0;
-        let mut preds = [null_mut(); MAX_LEVEL + 1];
-        let mut succs = [null_mut(); MAX_LEVEL + 1];
+        self.insert_with(key, || value, false, &epoch::pin())
+            .is_ok()
+    }
 
-        let key = Key::Entry(key);
-        let new_entry = Box::into_raw(Box::new(Entry::new(key, Some(value), top_level)));
-        let key_ref = unsafe { &(*new_entry).key };
+    /// Inserts `key` with `value`, replacing the value already held for the
+    /// key, and returns a reference to the value it replaced.
+    ///
+    /// The value is swapped in with a single atomic exchange, so the key
+    /// never looks absent and a concurrent `remove` of the key takes either
+    /// the old value or the new one. As with `remove`, other threads may
+    /// still be reading the old value, so it stays behind a reference and is
+    /// reclaimed once that is dropped.
+    pub fn insert(&self, key: K, value: V) -> Option<EntryRef<'_, K, V>> {
+        let guard = epoch::pin();
+
+        let (entry, old_value) = match self.insert_with(key, || value, true, &guard) {
+            Ok(_) => return None,
+            Err((entry, old_value)) => (entry, old_value),
+        };
+
+        unsafe {
+            guard.defer_destroy(old_value);
+            let old_value = old_value.as_raw();
+
+            Some(EntryRef::new(&*entry, &*old_value, guard))
+        }
+    }
+
+    /// Replaces the value of `key` with `value` if the key is present, and
+    /// returns a reference to the value it replaced. Unlike [`insert`]
+    /// (Self::insert), an absent key stays absent and `value` is dropped.
+    pub fn replace<Q>(&self, key: &Q, value: V) -> Option<EntryRef<'_, K, V>>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        self.swap_value_if(key, value, |_| true).ok()
+    }
+
+    /// Replaces the value of `key` with `new` if it is present and its value
+    /// equals `current`, and returns a reference to the value it replaced.
+    ///
+    /// The check and the swap are one atomic step: the swap only succeeds if
+    /// the value seen equal to `current` is still the one in place, so two
+    /// threads moving the same value along never both succeed. On failure
+    /// the value found, if any, is handed back along with `new`.
+    pub fn compare_and_swap<Q>(
+        &self,
+        key: &Q,
+        current: &V,
+        new: V,
+    ) -> Result<EntryRef<'_, K, V>, CompareAndSwapError<'_, K, V>>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+        V: PartialEq,
+    {
+        self.swap_value_if(key, new, |value| value == current)
+    }
+
+    /// Swaps `new` in as the value of `key` if the key is present and
+    /// `condition` holds for its value at the moment of the swap.
+    fn swap_value_if<Q, F>(
+        &self,
+        key: &Q,
+        new: V,
+        condition: F,
+    ) -> Result<EntryRef<'_, K, V>, CompareAndSwapError<'_, K, V>>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+        F: Fn(&V) -> bool,
+    {
+        let guard = epoch::pin();
+        let mut preds = [null_mut(); MAX_LEVEL];
+        let mut succs = [null_mut(); MAX_LEVEL];
+
+        let mut new = Owned::new(new);
 
         loop {
-            let found = self.find(key_ref, &mut preds, &mut succs);
+            let Some((entry, value)) = self.search(key, &mut preds, &mut succs, &guard) else {
+                return Err(CompareAndSwapError {
+                    current: None,
+                    new: *new.into_box(),
+                });
+            };
 
-            if found {
-                return false;
+            let entry = unsafe { &*entry };
+
+            if !condition(unsafe { value.deref() }) {
+                let value = value.as_raw();
+
+                return Err(CompareAndSwapError {
+                    current: Some(EntryRef::new(entry, unsafe { &*value }, guard)),
+                    new: *new.into_box(),
+                });
+            }
+
+            match entry
+                .value()
+                .compare_exchange(value, new, AcqRel, Acquire, &guard)
+            {
+                Ok(_) => unsafe {
+                    guard.defer_destroy(value);
+                    let value = value.as_raw();
+
+                    return Ok(EntryRef::new(entry, &*value, guard));
+                },
+                // Replaced or removed since it was read; look again.
+                Err(err) => new = err.new,
+            }
+        }
+    }
+
+    /// Returns the value of `key`, inserting the result of `f` first if the
+    /// key is absent. Check and insert are one atomic step: of several
+    /// threads racing on the same key, exactly one inserts and all of them
+    /// get its value.
+    ///
+    /// `f` is called at most once, and only once the key has been seen
+    /// absent. Its result is dropped if another thread inserts the key first.
+    pub fn get_or_insert_with<F>(&self, key: K, f: F) -> EntryRef<'_, K, V>
+    where
+        F: FnOnce() -> V,
+    {
+        let guard = epoch::pin();
+        let (Ok((entry, value)) | Err((entry, value))) = self.insert_with(key, f, false, &guard);
+        let value = value.as_raw();
+
+        unsafe { EntryRef::new(&*entry, &*value, guard) }
+    }
+
+    /// Inserts `key` with `value` unless it is present, in which case both
+    /// are handed back untouched.
+    pub fn try_insert(&self, key: K, value: V) -> Result<(), (K, V)> {
+        let guard = &epoch::pin();
+        let top_level = self.level_generator.random_level(self.p, self.max_level);
+        self.raise_height(top_level + 1);
+        let mut preds = [null_mut(); MAX_LEVEL];
+        let mut succs = [null_mut(); MAX_LEVEL];
+
+        let new_entry = Entry::alloc(Key::Entry(key), Some(value), top_level);
+        let key_ref = unsafe { entry_key(new_entry) };
+
+        loop {
+            if self
+                .search(key_ref, &mut preds, &mut succs, guard)
+                .is_some()
+            {
+                // Never published, so no other thread can be looking at it.
+                return Err(unsafe { Entry::into_key_value(new_entry) });
+            }
+
+            if unsafe { self.link(new_entry, &mut preds, &mut succs, guard) } {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Inserts `key` with the value made by `f` if it is absent. Otherwise
+    /// `replace` decides whether the value made by `f` replaces the present
+    /// one.
+    ///
+    /// Returns the new entry and value, or the entry that was already there
+    /// with the value it held. Both stay readable until `guard` is dropped.
+    fn insert_with<'g, F>(
+        &self,
+        key: K,
+        f: F,
+        replace: bool,
+        guard: &'g Guard,
+    ) -> Result<Found<'g, K, V, C>, Found<'g, K, V, C>>
+    where
+        F: FnOnce() -> V,
+    {
+        let top_level = self.level_generator.random_level(self.p, self.max_level);
+        self.raise_height(top_level + 1);
+        let mut preds = [null_mut(); MAX_LEVEL];
+        let mut succs = [null_mut(); MAX_LEVEL];
+
+        // The value is only made once it is needed and the entry once the
+        // key has been seen absent; both are kept across retries.
+        let mut key = Some(key);
+        let mut f = Some(f);
+        let mut value = None;
+        let mut new_entry: *mut Entry<K, V, C> = null_mut();
+
+        loop {
+            let present = match &key {
+                Some(key) => self.search(key, &mut preds, &mut succs, guard),
+                None => {
+                    let key = unsafe { entry_key(new_entry) };
+                    self.search(key, &mut preds, &mut succs, guard)
+                }
+            };
+
+            if let Some((entry, current)) = present {
+                if replace {
+                    let new_value = value
+                        .take()
+                        .unwrap_or_else(|| Owned::new(f.take().unwrap()()));
+
+                    if let Err(err) = unsafe { &*entry }
+                        .value()
+                        .compare_exchange(current, new_value, AcqRel, Acquire, guard)
+                    {
+                        value = Some(err.new);
+                        continue;
+                    }
+                }
+
+                if !new_entry.is_null() {
+                    // Never published, so no other thread can be looking at it.
+                    unsafe { Entry::free(new_entry) };
+                }
+
+                return Err((entry, current));
+            }
+
+            if value.is_none() {
+                value = Some(Owned::new(f.take().unwrap()()));
+            }
+
+            if let Some(key) = key.take() {
+                new_entry = Entry::alloc(Key::Entry(key), None, top_level);
             }
 
             unsafe {
-                for level in bottom_level..=top_level {
-                    (*new_entry).nexts[level].store(succs[level], false, Relaxed);
+                (*new_entry).value().store(value.take().unwrap(), Relaxed);
+                let new_value = (*new_entry).value().load(Relaxed, guard);
+
+                if self.link(new_entry, &mut preds, &mut succs, guard) {
+                    return Ok((new_entry, new_value));
                 }
 
-                let pred = &mut *preds[bottom_level];
-                let succ = &mut *succs[bottom_level];
+                // Still unpublished; take the value back for the retry.
+                let new_value = (*new_entry).value().swap(Shared::null(), Relaxed, guard);
+                value = Some(new_value.into_owned());
+            }
+        }
+    }
 
-                if !pred.nexts[bottom_level]
+    /// Links the unpublished `new_entry` between the `preds` and `succs`
+    /// found by a search for its key. Returns `false`, leaving the entry
+    /// unpublished, if the bottom level changed since the search.
+    ///
+    /// Once this returns `true`, the entry belongs to the list and the caller
+    /// may only read it while `guard` is pinned.
+    unsafe fn link<const N: usize>(
+        &self,
+        new_entry: *mut Entry<K, V, C>,
+        preds: &mut [*mut Entry<K, V, C>; N],
+        succs: &mut [*mut Entry<K, V, C>; N],
+        guard: &Guard,
+    ) -> bool {
+        let bottom_level = 0;
+        let top_level = (*new_entry).top_level;
+        let key_ref = entry_key(new_entry);
+
+        for (next, &succ) in (*new_entry).nexts().iter().zip(succs.iter()) {
+            next.store(succ, false, Relaxed);
+        }
+
+        let pred = &mut *preds[bottom_level];
+        let succ = &mut *succs[bottom_level];
+
+        if pred.nexts()[bottom_level]
+            .compare_exchange(succ, new_entry, false, false, Release, Relaxed)
+            .is_err()
+        {
+            return false;
+        }
+
+        self.len.increment();
+
+        // TODO: why bottom level?
+        'link: for level in bottom_level + 1..=top_level {
+            loop {
+                let pred = &mut *preds[level];
+                let succ = &mut *succs[level];
+
+                if pred.nexts()[level]
                     .compare_exchange(succ, new_entry, false, false, Release, Relaxed)
                     .is_ok()
                 {
-                    continue;
+                    break;
                 }
 
-                // TODO: why bottom level?
-                for level in bottom_level + 1..=top_level {
-                    loop {
-                        let pred = &mut *preds[level];
-                        let succ = &mut *succs[level];
-
-                        if pred.nexts[level]
-                            .compare_exchange(succ, new_entry, false, false, Release, Relaxed)
-                            .is_ok()
-                        {
-                            break;
-                        }
+                self.find(key_ref, preds, succs);
 
-                        self.find(key_ref, &mut preds, &mut succs);
-                    }
+                // Follow the new successor, unless a remover has already
+                // frozen this level.
+                let (next, marked) = (*new_entry).nexts()[level].load(Acquire);
+
+                if marked
+                    || (*new_entry).nexts()[level]
+                        .compare_exchange(next, succs[level], false, false, Release, Relaxed)
+                        .is_err()
+                {
+                    break 'link;
                 }
             }
+        }
 
-            return true;
+        // A remover may have run its cleanup search before we linked the
+        // upper levels; search again so that no level keeps pointing at the
+        // entry once it is retired.
+        if (*new_entry).nexts()[bottom_level].load(Acquire).1 {
+            self.find(key_ref, preds, succs);
         }
+
+        self.release(new_entry, guard);
+
+        true
     }
 
-    pub fn remove(&self, key: K) -> Option<V> {
-        let bottom_level = 0;
+    /// Removes `key` and returns a reference to its entry, which stays
+    /// readable until the reference is dropped.
+    pub fn remove<Q>(&self, key: &Q) -> Option<EntryRef<'_, K, V>>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        let guard = epoch::pin();
+        let (entry, value) = self.unlink(key, &guard)?;
 
-        let mut preds = [null_mut(); MAX_LEVEL + 1];
-        let mut succs = [null_mut(); MAX_LEVEL + 1];
+        unsafe {
+            guard.defer_destroy(value);
+            let value = value.as_raw();
+
+            Some(EntryRef::new(&*entry, &*value, guard))
+        }
+    }
+
+    /// Removes the entry holding `key` and retires it, handing its value to
+    /// the caller. The entry is not reclaimed before `guard` is dropped.
+    fn unlink<'g, Q>(&self, key: &Q, guard: &'g Guard) -> Option<Found<'g, K, V, C>>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        let mut preds = [null_mut(); MAX_LEVEL];
+        let mut succs = [null_mut(); MAX_LEVEL];
 
-        let key = Key::Entry(key);
         loop {
-            let found = self.find(&key, &mut preds, &mut succs);
+            let (victim, _) = self.search(key, &mut preds, &mut succs, guard)?;
+
+            if let Some(value) = unsafe { self.claim(victim, guard) } {
+                return Some((victim, value));
+            }
+        }
+    }
+
+    /// Removes `entry` by setting the mark bit of its bottom link, then takes
+    /// its value out and retires it. Returns the value, or `None` if another
+    /// thread marked the entry first; of several threads claiming the same
+    /// entry, exactly one succeeds.
+    ///
+    /// Setting the bottom mark is the moment the key is removed. A replace
+    /// that read the value before then may still swap it afterwards; it is
+    /// ordered just before the removal, and its value is the one taken out.
+    unsafe fn claim<'g>(
+        &self,
+        entry: *mut Entry<K, V, C>,
+        guard: &'g Guard,
+    ) -> Option<Shared<'g, V>> {
+        let nexts = (*entry).nexts();
 
-            if !found {
+        // The upper levels are marked first, so that an entry marked at the
+        // bottom is marked on every level and one search unlinks it.
+        for next in nexts[1..].iter().rev() {
+            let (mut ptr, mut marked) = next.load(Acquire);
+
+            while !marked {
+                let _ = next.compare_exchange(ptr, ptr, false, true, Release, Relaxed);
+                (ptr, marked) = next.load(Acquire);
+            }
+        }
+
+        let (mut succ, mut marked) = nexts[0].load(Acquire);
+
+        loop {
+            if marked {
                 return None;
             }
 
-            let node_to_remove = unsafe { &mut *succs[bottom_level] };
+            // Fails when an entry was linked after this one in the meantime.
+            match nexts[0].compare_exchange(succ, succ, false, true, AcqRel, Acquire) {
+                Ok(_) => break,
+                Err(current) => (succ, marked) = current,
+            }
+        }
+
+        self.len.decrement();
 
-            for level in (bottom_level + 1..=node_to_remove.top_level).rev() {
-                let (mut ptr, mut marked) = node_to_remove.nexts[level].load(Acquire);
+        let value = (*entry).value().swap(Shared::null(), AcqRel, guard);
 
-                while !marked {
-                    let _ = node_to_remove.nexts[level]
-                        .compare_exchange(ptr, ptr, false, true, Release, Relaxed);
-                    (ptr, marked) = node_to_remove.nexts[level].load(Acquire);
+        self.unlink_marked(entry);
+        self.release(entry, guard);
+
+        Some(value)
+    }
+
+    /// Unlinks a marked entry from every level by searching for its key.
+    unsafe fn unlink_marked(&self, entry: *mut Entry<K, V, C>) {
+        let mut preds = [null_mut(); MAX_LEVEL];
+        let mut succs = [null_mut(); MAX_LEVEL];
+
+        self.raise_height((*entry).top_level + 1);
+        self.find(entry_key(entry), &mut preds, &mut succs);
+    }
+
+    /// Removes the entry with the smallest key and returns a reference to
+    /// it, which stays readable until the reference is dropped.
+    ///
+    /// Threads popping at once each walk the bottom level from the head,
+    /// passing over entries already marked, and claim the first one left by
+    /// setting its mark bit, the same way `remove` claims an entry. Every
+    /// entry is popped by exactly one of them. An entry added behind a walk
+    /// may be passed over by that pop.
+    pub fn pop_first(&self) -> Option<EntryRef<'_, K, V>> {
+        let guard = epoch::pin();
+
+        loop {
+            let (entry, _) = self.first_entry(&guard)?;
+
+            if let Some(value) = unsafe { self.claim(entry, &guard) } {
+                unsafe {
+                    guard.defer_destroy(value);
+                    let value = value.as_raw();
+
+                    return Some(EntryRef::new(&*entry, &*value, guard));
                 }
             }
+        }
+    }
 
-            let (mut succ, _) = node_to_remove.nexts[bottom_level].load(Acquire);
+    /// Removes the entry with the largest key and returns a reference to it.
+    /// Like [`pop_first`](Self::pop_first), but from the other end.
+    pub fn pop_last(&self) -> Option<EntryRef<'_, K, V>> {
+        let guard = epoch::pin();
 
-            loop {
-                match node_to_remove.nexts[bottom_level]
-                    .compare_exchange(succ, succ, false, true, Release, Acquire)
-                {
-                    Ok(_) => {
-                        self.find(&key, &mut preds, &mut succs);
-                        return Some(node_to_remove.value.take().unwrap());
+        loop {
+            let (entry, _) = self.last_entry(&guard)?;
+
+            if let Some(value) = unsafe { self.claim(entry, &guard) } {
+                unsafe {
+                    guard.defer_destroy(value);
+                    let value = value.as_raw();
+
+                    return Some(EntryRef::new(&*entry, &*value, guard));
+                }
+            }
+        }
+    }
+
+    /// Returns a reference to the entry with the smallest key. Same as
+    /// [`first`](Self::first), named to go with [`pop_first`](Self::pop_first).
+    pub fn peek_first(&self) -> Option<EntryRef<'_, K, V>> {
+        self.first()
+    }
+
+    /// Returns a reference to the entry with the largest key. Same as
+    /// [`last`](Self::last).
+    pub fn peek_last(&self) -> Option<EntryRef<'_, K, V>> {
+        self.last()
+    }
+
+    /// Returns the entry with the largest key less than or equal to `key`.
+    pub fn floor<Q>(&self, key: &Q) -> Option<EntryRef<'_, K, V>>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        let guard = epoch::pin();
+        let (entry, value) =
+            self.last_where(|current| current.cmp_by(key, &self.key_cmp).is_le(), &guard)?;
+        let value = value.as_raw();
+
+        unsafe { Some(EntryRef::new(&*entry, &*value, guard)) }
+    }
+
+    /// Returns the entry with the smallest key greater than or equal to
+    /// `key`.
+    pub fn ceiling<Q>(&self, key: &Q) -> Option<EntryRef<'_, K, V>>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        let guard = epoch::pin();
+        let mut preds = [null_mut(); MAX_LEVEL];
+        let mut succs = [null_mut(); MAX_LEVEL];
+
+        self.find(key, &mut preds, &mut succs);
+
+        let (entry, value) = self.first_from(succs[0], |_| false, &guard)?;
+        let value = value.as_raw();
+
+        unsafe { Some(EntryRef::new(&*entry, &*value, guard)) }
+    }
+
+    /// Returns the entry with the largest key strictly less than `key`.
+    pub fn lower<Q>(&self, key: &Q) -> Option<EntryRef<'_, K, V>>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        let guard = epoch::pin();
+        let (entry, value) =
+            self.last_where(|current| current.cmp_by(key, &self.key_cmp).is_lt(), &guard)?;
+        let value = value.as_raw();
+
+        unsafe { Some(EntryRef::new(&*entry, &*value, guard)) }
+    }
+
+    /// Returns the entry with the smallest key strictly greater than `key`.
+    pub fn higher<Q>(&self, key: &Q) -> Option<EntryRef<'_, K, V>>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        let guard = epoch::pin();
+        let mut preds = [null_mut(); MAX_LEVEL];
+        let mut succs = [null_mut(); MAX_LEVEL];
+
+        self.find(key, &mut preds, &mut succs);
+
+        let (entry, value) = self.first_from(
+            succs[0],
+            |current| current.cmp_by(key, &self.key_cmp).is_eq(),
+            &guard,
+        )?;
+        let value = value.as_raw();
+
+        unsafe { Some(EntryRef::new(&*entry, &*value, guard)) }
+    }
+
+    /// Returns the entry with the first key not less than `key`, as C++'s
+    /// `lower_bound` does; the same entry as [`ceiling`](Self::ceiling).
+    pub fn lower_bound<Q>(&self, key: &Q) -> Option<EntryRef<'_, K, V>>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        self.ceiling(key)
+    }
+
+    /// Returns the entry with the first key greater than `key`, as C++'s
+    /// `upper_bound` does; the same entry as [`higher`](Self::higher).
+    pub fn upper_bound<Q>(&self, key: &Q) -> Option<EntryRef<'_, K, V>>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        self.higher(key)
+    }
+
+    /// Returns the entry with the smallest key.
+    pub fn first(&self) -> Option<EntryRef<'_, K, V>> {
+        let guard = epoch::pin();
+        let (entry, value) = self.first_entry(&guard)?;
+        let value = value.as_raw();
+
+        unsafe { Some(EntryRef::new(&*entry, &*value, guard)) }
+    }
+
+    /// Returns the entry with the largest key.
+    pub fn last(&self) -> Option<EntryRef<'_, K, V>> {
+        let guard = epoch::pin();
+        let (entry, value) = self.last_entry(&guard)?;
+        let value = value.as_raw();
+
+        unsafe { Some(EntryRef::new(&*entry, &*value, guard)) }
+    }
+
+    fn first_entry<'g>(&self, guard: &'g Guard) -> Option<Found<'g, K, V, C>> {
+        let first = unsafe { (*self.head).nexts()[0].load_ptr(Acquire) };
+
+        self.first_from(first, |_| false, guard)
+    }
+
+    fn last_entry<'g>(&self, guard: &'g Guard) -> Option<Found<'g, K, V, C>> {
+        self.last_where(|current| !matches!(current, Key::Tail), guard)
+    }
+
+    /// Walks the bottom level from `current` to the first entry that is
+    /// present and not skipped.
+    fn first_from<'g, F>(
+        &self,
+        mut current: *mut Entry<K, V, C>,
+        skip: F,
+        guard: &'g Guard,
+    ) -> Option<Found<'g, K, V, C>>
+    where
+        F: Fn(&Key<K>) -> bool,
+    {
+        loop {
+            let entry = unsafe { &*current };
+
+            if let Key::Tail = entry.key {
+                return None;
+            }
+
+            if let Some(value) = entry.present_value(guard) {
+                if !skip(&entry.key) {
+                    return Some((current, value));
+                }
+            }
+
+            current = entry.nexts()[0].load_ptr(Acquire);
+        }
+    }
+
+    /// Finds the last entry whose key is `before` the target and that is
+    /// present, descending like `find` while `before` holds.
+    ///
+    /// The bottom level only links forward, so an entry found removed cannot
+    /// be stepped back from; it is unlinked on its remover's behalf and the
+    /// descent starts over.
+    fn last_where<'g, F>(&self, before: F, guard: &'g Guard) -> Option<Found<'g, K, V, C>>
+    where
+        F: Fn(&Key<K>) -> bool,
+    {
+        loop {
+            let mut pred = unsafe { &*self.head };
+
+            for level in (0..self.current_height.load(Relaxed)).rev() {
+                let mut current = unsafe { &*pred.nexts()[level].load_ptr(Acquire) };
+
+                loop {
+                    let (mut succ, mut marked) = current.nexts()[level].load(Acquire);
+
+                    while marked {
+                        current = unsafe { &*succ };
+                        (succ, marked) = current.nexts()[level].load(Acquire);
                     }
-                    Err((actual_succ, marked)) => {
-                        succ = actual_succ;
-                        if marked {
-                            return None;
-                        }
+
+                    if !before(&current.key) {
+                        break;
                     }
-                };
+
+                    pred = current;
+                    current = unsafe { &*succ };
+                }
             }
+
+            if let Key::Head = pred.key {
+                return None;
+            }
+
+            if let Some(value) = pred.present_value(guard) {
+                return Some((pred as *const _ as *mut _, value));
+            }
+
+            unsafe { self.unlink_marked(pred as *const _ as *mut _) };
+        }
+    }
+
+    /// Searches for `key` like `find`, but only counts an entry as present
+    /// while its bottom link is unmarked. Returns that entry and its value.
+    ///
+    /// An entry found marked is already marked on every level, so the
+    /// retried search unlinks it.
+    fn search<'g, Q, const N: usize>(
+        &self,
+        key: &Q,
+        preds: &mut [*mut Entry<K, V, C>; N],
+        succs: &mut [*mut Entry<K, V, C>; N],
+        guard: &'g Guard,
+    ) -> Option<Found<'g, K, V, C>>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        loop {
+            if !self.find(key, preds, succs) {
+                return None;
+            }
+
+            let entry = succs[0];
+
+            if let Some(value) = unsafe { (*entry).present_value(guard) } {
+                return Some((entry, value));
+            }
+        }
+    }
+
+    /// Gives up the caller's claim on `entry`. Both the thread that added it
+    /// and the one that removed it hold a claim, since either may still be
+    /// linking or unlinking it; the last one out retires the entry.
+    unsafe fn release(&self, entry: *mut Entry<K, V, C>, guard: &Guard) {
+        if (*entry).owners.fetch_sub(1, AcqRel) == 1 {
+            guard.defer_unchecked(move || Entry::free(entry));
         }
     }
 
-    fn find<'a, 'b, const N: usize>(
+    /// Raises the current height to at least `height`. A later read of it
+    /// by the same thread sees no less.
+    fn raise_height(&self, height: usize) {
+        if self.current_height.load(Relaxed) < height {
+            self.current_height.fetch_max(height, Relaxed);
+        }
+    }
+
+    /// Searches for `key` from the current height down, unlinking the marked
+    /// entries it passes and filling `preds` and `succs` on the levels
+    /// searched. Returns whether an entry with `key` is linked at the bottom.
+    ///
+    /// The height read may be stale, which only leaves express levels
+    /// unused, except that callers take the levels of the entry found as
+    /// searched. If it reaches above them, the height is raised past it and
+    /// the search starts over. An inserter raises the height before its
+    /// search, and a remover before its cleanup search, for the same reason.
+    fn find<'a, 'b, Q, const N: usize>(
         &'a self,
-        key: &Key<K>,
+        key: &Q,
         preds: &'b mut [*mut Entry<K, V, C>; N],
         succs: &'b mut [*mut Entry<K, V, C>; N],
     ) -> bool
     where
         'a: 'b,
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
     {
         let bottom_level = 0;
 
         let mut current_ptr = null_mut();
-        let mut marked = false;
-
-        let mut tries = 0;
 
         'retry: loop {
-            tries += 1;
-
-            if tries > 100 {
-                panic!("try to much");
-            }
+            let height = self.current_height.load(Relaxed);
 
             let mut pred = self.head;
-            for level in (bottom_level..=MAX_LEVEL).rev() {
-                current_ptr = unsafe { (*pred).nexts[level].load_ptr(Acquire) };
+            for level in (bottom_level..height).rev() {
+                current_ptr = unsafe { (*pred).nexts()[level].load_ptr(Acquire) };
 
                 loop {
                     if current_ptr.is_null() {
@@ -221,11 +877,11 @@ where
                     }
 
                     let mut current = unsafe { current_ptr.as_mut().unwrap() };
-                    let (mut succ_ptr, mut marked) = current.nexts[level].load(Acquire);
+                    let (mut succ_ptr, mut marked) = current.nexts()[level].load(Acquire);
 
                     while marked {
                         unsafe {
-                            match (*pred).nexts[level].compare_exchange(
+                            match (*pred).nexts()[level].compare_exchange(
                                 current_ptr,
                                 succ_ptr,
                                 false,
@@ -234,25 +890,21 @@ where
                                 Acquire,
                             ) {
                                 Ok(_) => {
-                                    current_ptr = (*pred).nexts[level].load_ptr(Acquire);
+                                    current_ptr = (*pred).nexts()[level].load_ptr(Acquire);
                                     if current_ptr.is_null() {
                                         return false;
                                     }
                                     current = current_ptr.as_mut().unwrap();
-                                    (succ_ptr, marked) = current.nexts[level].load(Acquire);
+                                    (succ_ptr, marked) = current.nexts()[level].load(Acquire);
                                 }
-                                Err((actual, _)) => {
-                                    // println!(
-                                    //     "actual: {:p}, current_ptr: {:p}",
-                                    //     actual, current_ptr
-                                    // );
+                                Err(_) => {
                                     continue 'retry;
                                 }
                             }
                         }
                     }
 
-                    if current.key < *key && !succ_ptr.is_null() {
+                    if current.key.cmp_by(key, &self.key_cmp).is_lt() && !succ_ptr.is_null() {
                         pred = current;
                         current_ptr = succ_ptr;
                     } else {
@@ -264,30 +916,227 @@ where
                 succs[level] = current_ptr;
             }
 
-            return unsafe { !current_ptr.is_null() && (*current_ptr).key == *key };
+            let found = unsafe {
+                !current_ptr.is_null() && (*current_ptr).key.cmp_by(key, &self.key_cmp).is_eq()
+            };
+
+            if found {
+                let top_level = unsafe { (*current_ptr).top_level };
+
+                if top_level >= height {
+                    self.raise_height(top_level + 1);
+                    continue 'retry;
+                }
+            }
+
+            return found;
         }
     }
 
-    pub fn get(&self, key: K) -> Option<&V> {
-        let mut pred = self.head;
+    /// Looks up `key` without helping to unlink marked entries, so a reader
+    /// never retries and finishes in a bounded number of steps.
+    ///
+    /// An entry counts as present while its bottom link is unmarked; the
+    /// load of its value, checked against the mark right after, is the
+    /// linearization point.
+    pub fn get<Q>(&self, key: &Q) -> Option<EntryRef<'_, K, V>>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        let guard = epoch::pin();
+
+        let mut pred = unsafe { &*self.head };
+        let mut current = pred;
+
+        for level in (0..self.current_height.load(Relaxed)).rev() {
+            current = unsafe { &*pred.nexts()[level].load_ptr(Acquire) };
+
+            loop {
+                let (mut succ, mut marked) = current.nexts()[level].load(Acquire);
+
+                while marked {
+                    current = unsafe { &*succ };
+                    (succ, marked) = current.nexts()[level].load(Acquire);
+                }
+
+                if current.key.cmp_by(key, &self.key_cmp).is_lt() {
+                    pred = current;
+                    current = unsafe { &*succ };
+                } else {
+                    break;
+                }
+            }
+        }
+
+        if !current.key.cmp_by(key, &self.key_cmp).is_eq() {
+            return None;
+        }
+
+        let value = current.present_value(&guard)?.as_raw();
+
+        Some(EntryRef::new(current, unsafe { &*value }, guard))
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Returns the number of entries.
+    ///
+    /// Every successful add and remove updates a counter split per thread.
+    /// The count is exact while no add or remove is running; while they are,
+    /// it may include some of them and not others, so it is only an
+    /// approximation.
+    pub fn len(&self) -> usize {
+        self.len.sum()
+    }
+
+    /// Same as `len() == 0`, with the same caveat under concurrent writes.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns a weakly consistent iterator over the entries, in key order.
+    /// See [`Iter`] for which concurrent changes it observes.
+    pub fn iter(&self) -> Iter<'_, K, V, C> {
+        Iter::new(self)
+    }
+
+    /// Returns a weakly consistent iterator over the entries whose keys lie
+    /// in `range`, in order. The start is located by a search from the head,
+    /// so only the entries in the range are visited.
+    pub fn range<Q, R>(&self, range: R) -> Range<'_, Q, R, K, V, C>
+    where
+        Q: ?Sized,
+        R: RangeBounds<Q>,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        let guard = epoch::pin();
+
+        let start = match range.start_bound() {
+            Bound::Unbounded => unsafe { (*self.head).nexts()[0].load_ptr(Acquire) },
+            Bound::Included(key) | Bound::Excluded(key) => {
+                let mut preds = [null_mut(); MAX_LEVEL];
+                let mut succs = [null_mut(); MAX_LEVEL];
 
-        let key = Key::Entry(key);
+                let found = self.find(key, &mut preds, &mut succs);
 
-        for level in (0..MAX_LEVEL).rev() {
-            let (mut current_ptr, mut marked) = unsafe { (*pred).nexts[level].load(Relaxed) };
-            let mut current = unsafe { current_ptr.as_mut().unwrap() };
+                if found && matches!(range.start_bound(), Bound::Excluded(_)) {
+                    unsafe { (*succs[0]).nexts()[0].load_ptr(Acquire) }
+                } else {
+                    succs[0]
+                }
+            }
+        };
 
-            todo!("");
+        Range::new(self, start, range, guard)
+    }
+}
+
+impl<K, V, C> Drop for SkipList<K, V, C>
+where
+    C: Comparator<K>,
+{
+    fn drop(&mut self) {
+        // Removed entries are already unlinked from every level and left to
+        // the collector, so the bottom level holds exactly the entries we
+        // still own, head and tail included.
+        let mut current = self.head;
+
+        while !current.is_null() {
+            let next = unsafe { (*current).nexts()[0].load_ptr(Relaxed) };
+            unsafe { Entry::free(current) };
+            current = next;
         }
+    }
+}
+
+/// Borrows the key of an entry that holds a real one.
+unsafe fn entry_key<'a, K, V, C>(entry: *mut Entry<K, V, C>) -> &'a K
+where
+    C: Comparator<K>,
+{
+    let Key::Entry(key) = &(*entry).key else {
+        unreachable!("head and tail are never inserted")
+    };
+
+    key
+}
+
+impl<K, V, C> OrderedMap<K, V> for SkipList<K, V, C>
+where
+    K: Send + 'static,
+    V: Send + 'static,
+    C: Comparator<K>,
+{
+    type Ref<'a>
+        = EntryRef<'a, K, V>
+    where
+        Self: 'a;
 
-        return None;
+    fn add(&mut self, key: K, value: V) -> bool {
+        SkipList::add(self, key, value)
+    }
+
+    fn try_insert(&mut self, key: K, value: V) -> Result<(), (K, V)> {
+        SkipList::try_insert(self, key, value)
+    }
+
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        // `&mut self` rules out readers, so nothing needs to wait for them:
+        // the old value is moved out right away.
+        let guard = unsafe { epoch::unprotected() };
+        let (_, old_value) = self.insert_with(key, || value, true, guard).err()?;
+
+        Some(*unsafe { old_value.into_owned() }.into_box())
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        // `&mut self` rules out readers, so nothing needs to wait for them:
+        // the entry is freed and the value moved out right away.
+        let guard = unsafe { epoch::unprotected() };
+        let (_, value) = self.unlink(key, guard)?;
+
+        Some(*unsafe { value.into_owned() }.into_box())
+    }
+
+    fn get(&self, key: &K) -> Option<EntryRef<'_, K, V>> {
+        SkipList::get(self, key)
+    }
+
+    fn len(&self) -> usize {
+        SkipList::len(self)
     }
 }
 
-static P: f32 = 0.5;
-const MAX_LEVEL: usize = 32;
+impl<K, V, C> ConcurrentOrderedMap<K, V> for SkipList<K, V, C>
+where
+    K: Send + Sync + 'static,
+    V: Send + Sync + 'static,
+    C: Comparator<K> + Sync,
+{
+    type Removed<'a>
+        = EntryRef<'a, K, V>
+    where
+        Self: 'a;
+
+    fn add_shared(&self, key: K, value: V) -> bool {
+        SkipList::add(self, key, value)
+    }
 
-fn random_level() -> usize {
-    let level = (f32::log2(1. - random::<f32>()) / f32::log2(1. - P)) as usize;
-    return min(level, MAX_LEVEL);
+    fn insert_shared(&self, key: K, value: V) -> Option<EntryRef<'_, K, V>> {
+        SkipList::insert(self, key, value)
+    }
+
+    fn remove_shared(&self, key: &K) -> Option<EntryRef<'_, K, V>> {
+        SkipList::remove(self, key)
+    }
 }
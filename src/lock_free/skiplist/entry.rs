@@ -1,67 +1,167 @@
+use std::alloc::{alloc, dealloc, handle_alloc_error, Layout};
+use std::borrow::Borrow;
 use std::cmp::Ordering;
-use std::fmt::Display;
-use std::sync::atomic::{AtomicBool, AtomicPtr};
-use std::sync::Mutex;
+use std::marker::PhantomData;
+use std::mem::offset_of;
+use std::ptr;
+use std::slice;
+use std::sync::atomic::{AtomicUsize, Ordering::*};
 
-use parking_lot::ReentrantMutex;
+use crossbeam_epoch::{self as epoch, Atomic, Guard, Shared};
 
 use crate::lock_free::markable_atomic_ptr::MarkableAtomicPtr;
+use crate::value_slot::ValueSlot;
+use crate::Comparator;
 
+/// An entry is allocated in one piece: this header, then its tower of
+/// `top_level + 1` links, then its value slot unless `V` needs none.
+///
+/// Marking the bottom link is what removes the entry; see
+/// `SkipList::claim`.
+#[repr(C)]
 pub struct Entry<K, V, C>
 where
-    K: Ord,
-    C: Fn(&K, &K) -> Ordering,
+    C: Comparator<K>,
 {
     pub key: Key<K>,
-    pub value: Option<V>,
     pub top_level: usize,
-    pub nexts: Vec<MarkableAtomicPtr<Entry<K, V, C>>>,
+    /// Claims held by the adding and the removing thread; see
+    /// `SkipList::release`.
+    pub owners: AtomicUsize,
+    tower: [MarkableAtomicPtr<Entry<K, V, C>>; 0],
+    _value: PhantomData<V>,
 }
 
 impl<K, V, C> Entry<K, V, C>
 where
-    K: Ord,
-    C: Fn(&K, &K) -> Ordering,
+    C: Comparator<K>,
 {
-    pub fn new(key: Key<K>, value: Option<V>, top_level: usize) -> Self {
-        let mut entry = Entry {
-            key,
-            value,
-            top_level,
-            nexts: Vec::with_capacity(top_level + 1),
+    /// Allocates an entry with `top_level + 1` null, unmarked links.
+    pub fn alloc(key: Key<K>, value: Option<V>, top_level: usize) -> *mut Self {
+        let layout = Self::layout(top_level);
+
+        unsafe {
+            let entry = alloc(layout) as *mut Self;
+
+            if entry.is_null() {
+                handle_alloc_error(layout);
+            }
+
+            entry.write(Entry {
+                key,
+                top_level,
+                owners: AtomicUsize::new(2),
+                tower: [],
+                _value: PhantomData,
+            });
+
+            let nexts = ptr::addr_of_mut!((*entry).tower) as *mut MarkableAtomicPtr<Self>;
+
+            for level in 0..=top_level {
+                nexts.add(level).write(Default::default());
+            }
+
+            if ValueSlot::<V>::STORED {
+                let slot = nexts.add(top_level + 1) as *mut Atomic<V>;
+                slot.write(value.map_or_else(Atomic::null, Atomic::new));
+            }
+
+            entry
+        }
+    }
+
+    /// Drops the key and value of an entry made by [`Entry::alloc`] and
+    /// frees the allocation, tower and slot included. Only for entries no
+    /// other thread can reach.
+    pub unsafe fn free(entry: *mut Self) {
+        let layout = Self::layout((*entry).top_level);
+        let value = (*entry).value().load(Relaxed, epoch::unprotected());
+
+        if !value.is_null() {
+            drop(value.into_owned());
+        }
+
+        ptr::drop_in_place(entry);
+        dealloc(entry as *mut u8, layout);
+    }
+
+    /// Takes apart an entry that holds a real key and a value, and frees
+    /// it. Only for entries no other thread can reach.
+    pub unsafe fn into_key_value(entry: *mut Self) -> (K, V) {
+        let layout = Self::layout((*entry).top_level);
+        let value = (*entry).value().load(Relaxed, epoch::unprotected());
+        let header = entry.read();
+
+        dealloc(entry as *mut u8, layout);
+
+        let Key::Entry(key) = header.key else {
+            unreachable!("head and tail hold no value")
         };
 
-        for _ in 0..=top_level {
-            entry.nexts.push(Default::default());
+        (key, *value.into_owned().into_box())
+    }
+
+    fn layout(top_level: usize) -> Layout {
+        let header = Layout::from_size_align(offset_of!(Self, tower), align_of::<Self>()).unwrap();
+        let tower = Layout::array::<MarkableAtomicPtr<Self>>(top_level + 1).unwrap();
+        let slot = Layout::array::<Atomic<V>>(ValueSlot::<V>::STORED as usize).unwrap();
+
+        // A slot is one word, like a link, so it follows the tower without
+        // padding.
+        let (layout, _) = header.extend(tower).unwrap();
+        let (layout, _) = layout.extend(slot).unwrap();
+
+        layout.pad_to_align()
+    }
+
+    pub fn nexts(&self) -> &[MarkableAtomicPtr<Self>] {
+        unsafe { slice::from_raw_parts(self.tower.as_ptr(), self.top_level + 1) }
+    }
+
+    /// The value, null in the head and tail and once taken out by the thread
+    /// that removed the entry. Replacing the value swaps the pointer, so
+    /// readers holding the old one can keep reading it.
+    pub fn value(&self) -> ValueSlot<'_, V> {
+        unsafe {
+            let slot = self.tower.as_ptr().add(self.top_level + 1) as *const Atomic<V>;
+            ValueSlot::new(slot)
         }
+    }
 
-        entry
+    /// Returns the value of the entry if it is present, that is, if its
+    /// bottom link is still unmarked once the value has been read. The value
+    /// was then in place while the entry was present.
+    pub fn present_value<'g>(&self, guard: &'g Guard) -> Option<Shared<'g, V>> {
+        let value = self.value().load(Acquire, guard);
+
+        if value.is_null() || self.nexts()[0].load(Acquire).1 {
+            None
+        } else {
+            Some(value)
+        }
     }
 }
 
-#[derive(PartialEq, Debug)]
-pub enum Key<K>
-where
-    K: Ord,
-{
+#[derive(Debug)]
+pub enum Key<K> {
     Head,
     Entry(K),
     Tail,
 }
 
-impl<K> PartialOrd for Key<K>
-where
-    K: Ord,
-{
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        match (self, other) {
-            (Key::Head, Key::Head) => Some(Ordering::Equal),
-            (Key::Head, _) => Some(Ordering::Less),
-            (_, Key::Head) => Some(Ordering::Greater),
-            (Key::Tail, Key::Tail) => Some(Ordering::Equal),
-            (Key::Tail, _) => Some(Ordering::Greater),
-            (_, Key::Tail) => Some(Ordering::Less),
-            (Key::Entry(k1), Key::Entry(k2)) => k1.partial_cmp(k2),
+impl<K> Key<K> {
+    /// Orders `self` against the real key `other` with `cmp`. The head sorts
+    /// before and the tail after every real key.
+    pub fn cmp_by<Q, C>(&self, other: &Q, cmp: &C) -> Ordering
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        match self {
+            Key::Head => Ordering::Less,
+            Key::Entry(key) => cmp.compare(key.borrow(), other),
+            Key::Tail => Ordering::Greater,
         }
     }
 }
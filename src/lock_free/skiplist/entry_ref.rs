@@ -0,0 +1,77 @@
+use std::borrow::Borrow;
+use std::ops::Deref;
+
+use crossbeam_epoch::Guard;
+
+use super::entry::{Entry, Key};
+use crate::Comparator;
+
+/// A reference to an entry of a lock-free [`SkipList`](super::SkipList).
+///
+/// The reference keeps the current thread pinned, so the entry is not
+/// reclaimed while it is alive, even if another thread removes it in the
+/// meantime. Holding on to it delays reclamation of every entry removed
+/// since, so drop it once done.
+pub struct EntryRef<'a, K, V> {
+    key: &'a K,
+    value: &'a V,
+    _guard: Guard,
+}
+
+impl<'a, K, V> EntryRef<'a, K, V> {
+    /// `value` is the value `entry` held, or still holds, while `guard` was
+    /// pinned.
+    pub(super) fn new<C>(entry: &'a Entry<K, V, C>, value: &'a V, guard: Guard) -> Self
+    where
+        C: Comparator<K>,
+    {
+        let Key::Entry(key) = &entry.key else {
+            unreachable!("head and tail are never handed out")
+        };
+
+        EntryRef {
+            key,
+            value,
+            _guard: guard,
+        }
+    }
+
+    /// Narrows the key to a part of it, keeping the entry pinned.
+    pub(crate) fn map_key<K2>(self, f: impl FnOnce(&'a K) -> &'a K2) -> EntryRef<'a, K2, V> {
+        EntryRef {
+            key: f(self.key),
+            value: self.value,
+            _guard: self._guard,
+        }
+    }
+
+    pub fn key(&self) -> &K {
+        self.key
+    }
+
+    pub fn value(&self) -> &V {
+        self.value
+    }
+}
+
+impl<K, V> Deref for EntryRef<'_, K, V> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        self.value
+    }
+}
+
+impl<K, V> Borrow<V> for EntryRef<'_, K, V> {
+    fn borrow(&self) -> &V {
+        self.value
+    }
+}
+
+/// The error of [`SkipList::compare_and_swap`](super::SkipList::compare_and_swap).
+pub struct CompareAndSwapError<'a, K, V> {
+    /// The entry found for the key, unless the key was absent.
+    pub current: Option<EntryRef<'a, K, V>>,
+    /// The value that was to be swapped in.
+    pub new: V,
+}
@@ -0,0 +1,187 @@
+use std::borrow::Borrow;
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::ops::RangeBounds;
+use std::sync::atomic::Ordering::*;
+
+use crossbeam_epoch::{self as epoch, Guard};
+
+use super::entry::{Entry, Key};
+use super::{EntryRef, SkipList};
+use crate::{past_end, Comparator};
+
+/// A weakly consistent iterator over the entries of a lock-free
+/// [`SkipList`], in key order.
+///
+/// The iterator walks the bottom level and never blocks or fails because of
+/// concurrent writers. What it observes of them is:
+///
+/// - Keys are yielded in strictly increasing order, so no key is yielded
+///   twice, even if it is removed and added again during the iteration.
+/// - A key that is present when the iterator is created and not removed
+///   before the iteration ends is always yielded.
+/// - A key whose removal completed before the iterator was created is never
+///   yielded.
+/// - A key added or removed while the iteration is in progress may or may
+///   not be yielded. If it is, it was present at the moment its entry was
+///   read.
+///
+/// The iterator keeps the current thread pinned, which delays reclamation
+/// of removed entries until it is dropped.
+pub struct Iter<'a, K, V, C>
+where
+    C: Comparator<K>,
+{
+    // Last entry yielded, the head at first and the tail once exhausted.
+    current: *mut Entry<K, V, C>,
+    // Keeps `current` alive after it is removed, so that its frozen links
+    // can still be followed.
+    guard: Guard,
+    _list: &'a SkipList<K, V, C>,
+}
+
+impl<'a, K, V, C> Iter<'a, K, V, C>
+where
+    C: Comparator<K>,
+{
+    pub(super) fn new(list: &'a SkipList<K, V, C>) -> Self {
+        Iter {
+            current: list.head,
+            guard: epoch::pin(),
+            _list: list,
+        }
+    }
+}
+
+impl<'a, K, V, C> Iterator for Iter<'a, K, V, C>
+where
+    C: Comparator<K>,
+{
+    type Item = EntryRef<'a, K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        unsafe {
+            if let Key::Tail = (*self.current).key {
+                return None;
+            }
+
+            let mut next = (*self.current).nexts()[0].load_ptr(Acquire);
+
+            loop {
+                if let Key::Tail = (*next).key {
+                    self.current = next;
+                    return None;
+                }
+
+                if let Some(value) = (*next).present_value(&self.guard) {
+                    self.current = next;
+                    return Some(EntryRef::new(&*next, &*value.as_raw(), epoch::pin()));
+                }
+
+                next = (*next).nexts()[0].load_ptr(Acquire);
+            }
+        }
+    }
+}
+
+impl<K, V, C> FusedIterator for Iter<'_, K, V, C> where C: Comparator<K> {}
+
+/// A weakly consistent iterator over the entries of a lock-free [`SkipList`]
+/// whose keys lie in a range, in key order. Created by [`SkipList::range`].
+///
+/// Observes concurrent changes the same way [`Iter`] does, restricted to the
+/// keys in the range.
+pub struct Range<'a, Q, R, K, V, C>
+where
+    Q: ?Sized,
+    C: Comparator<K>,
+{
+    list: &'a SkipList<K, V, C>,
+    // Next entry to look at.
+    current: *mut Entry<K, V, C>,
+    range: R,
+    guard: Guard,
+    _marker: PhantomData<fn(&Q)>,
+}
+
+impl<'a, Q, R, K, V, C> Range<'a, Q, R, K, V, C>
+where
+    Q: ?Sized,
+    C: Comparator<K>,
+{
+    /// `guard` must have been pinned before `current` was read.
+    pub(super) fn new(
+        list: &'a SkipList<K, V, C>,
+        current: *mut Entry<K, V, C>,
+        range: R,
+        guard: Guard,
+    ) -> Self {
+        Range {
+            list,
+            current,
+            range,
+            guard,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, Q, R, K, V, C> Iterator for Range<'a, Q, R, K, V, C>
+where
+    Q: ?Sized,
+    R: RangeBounds<Q>,
+    K: Borrow<Q>,
+    C: Comparator<K> + Comparator<Q>,
+{
+    type Item = EntryRef<'a, K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let entry = unsafe { &*self.current };
+
+            let Key::Entry(key) = &entry.key else {
+                return None;
+            };
+
+            if past_end(self.range.end_bound(), |end| {
+                self.list.key_cmp.compare(key.borrow(), end)
+            }) {
+                return None;
+            }
+
+            let value = entry.present_value(&self.guard);
+            self.current = entry.nexts()[0].load_ptr(Acquire);
+
+            if let Some(value) = value {
+                return Some(EntryRef::new(
+                    entry,
+                    unsafe { &*value.as_raw() },
+                    epoch::pin(),
+                ));
+            }
+        }
+    }
+}
+
+impl<Q, R, K, V, C> FusedIterator for Range<'_, Q, R, K, V, C>
+where
+    Q: ?Sized,
+    R: RangeBounds<Q>,
+    K: Borrow<Q>,
+    C: Comparator<K> + Comparator<Q>,
+{
+}
+
+impl<'a, K, V, C> IntoIterator for &'a SkipList<K, V, C>
+where
+    K: Send + 'static,
+    V: Send + 'static,
+    C: Comparator<K>,
+{
+    type Item = EntryRef<'a, K, V>;
+    type IntoIter = Iter<'a, K, V, C>;
+
+    fn into_iter(self) -> Iter<'a, K, V, C> {
+        self.iter()
+    }
+}
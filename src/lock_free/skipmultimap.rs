@@ -0,0 +1,182 @@
+use std::borrow::Borrow;
+use std::iter::FusedIterator;
+use std::ops::Bound;
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+
+use super::skiplist::{EntryRef, Iter, Range, SkipList};
+use crate::multi_key::{KeyOnly, MultiCmp, MultiKey};
+use crate::{Comparator, OrdComparator};
+
+/// A lock-free ordered multimap, which keeps every value inserted for a key.
+///
+/// Values for equal keys are kept in the order their inserts took a
+/// sequence number: each one is stored under its key and that number, so the
+/// underlying [`SkipList`] still holds unique keys and removing one value
+/// never disturbs the others.
+pub struct SkipMultiMap<K, V, C = OrdComparator>
+where
+    C: Comparator<K>,
+{
+    list: SkipList<MultiKey<K>, V, MultiCmp<C>>,
+    next_seq: AtomicU64,
+}
+
+impl<K, V> SkipMultiMap<K, V>
+where
+    K: Ord,
+{
+    pub fn new() -> Self {
+        SkipMultiMap::with_cmp(OrdComparator)
+    }
+}
+
+impl<K, V> Default for SkipMultiMap<K, V>
+where
+    K: Ord,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, C> SkipMultiMap<K, V, C>
+where
+    C: Comparator<K>,
+{
+    pub fn with_cmp(cmp: C) -> Self {
+        SkipMultiMap {
+            list: SkipList::with_cmp(MultiCmp(cmp)),
+            next_seq: AtomicU64::new(0),
+        }
+    }
+}
+
+impl<K, V, C> SkipMultiMap<K, V, C>
+where
+    K: Send + 'static,
+    V: Send + 'static,
+    C: Comparator<K>,
+{
+    /// Adds `value` under `key`, after every value already held for it.
+    pub fn insert(&self, key: K, value: V) {
+        let seq = self.next_seq.fetch_add(1, Relaxed);
+
+        self.list.add(MultiKey { key, seq }, value);
+    }
+
+    /// Returns a weakly consistent iterator over the values held for `key`,
+    /// in insertion order. See [`Iter`] for which concurrent changes it
+    /// observes.
+    pub fn get_all<'a, Q>(&'a self, key: &'a Q) -> GetAll<'a, Q, K, V, C>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        let key = KeyOnly::new(key);
+
+        GetAll(
+            self.list
+                .range::<KeyOnly<Q>, _>((Bound::Included(key), Bound::Included(key))),
+        )
+    }
+
+    /// Removes the oldest value held for `key` and returns a reference to
+    /// its entry, which stays readable until the reference is dropped.
+    pub fn remove_one<Q>(&self, key: &Q) -> Option<EntryRef<'_, K, V>>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        let entry = self.list.remove::<KeyOnly<Q>>(KeyOnly::new(key))?;
+
+        Some(entry.map_key(|key| &key.key))
+    }
+
+    /// Removes values held for `key` until there are none left. Returns how
+    /// many this call removed, which includes values inserted for `key`
+    /// while it runs and excludes those other threads removed first.
+    pub fn remove_all<Q>(&self, key: &Q) -> usize
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        let mut removed = 0;
+
+        while self.remove_one(key).is_some() {
+            removed += 1;
+        }
+
+        removed
+    }
+
+    /// Returns the number of values, counting each value of a key, with the
+    /// same caveat under concurrent writes as [`SkipList::len`].
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    /// Returns a weakly consistent iterator over every key and value, in key
+    /// order and then in insertion order.
+    pub fn iter(&self) -> MultiIter<'_, K, V, C> {
+        MultiIter(self.list.iter())
+    }
+}
+
+type KeyRange<'a, Q> = (Bound<&'a KeyOnly<Q>>, Bound<&'a KeyOnly<Q>>);
+
+/// A weakly consistent iterator over the values held for one key of a
+/// [`SkipMultiMap`], in insertion order. Created by
+/// [`SkipMultiMap::get_all`].
+pub struct GetAll<'a, Q, K, V, C>(
+    Range<'a, KeyOnly<Q>, KeyRange<'a, Q>, MultiKey<K>, V, MultiCmp<C>>,
+)
+where
+    Q: ?Sized,
+    C: Comparator<K>;
+
+impl<'a, Q, K, V, C> Iterator for GetAll<'a, Q, K, V, C>
+where
+    Q: ?Sized,
+    K: Borrow<Q>,
+    C: Comparator<K> + Comparator<Q>,
+{
+    type Item = EntryRef<'a, K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|entry| entry.map_key(|key| &key.key))
+    }
+}
+
+impl<Q, K, V, C> FusedIterator for GetAll<'_, Q, K, V, C>
+where
+    Q: ?Sized,
+    K: Borrow<Q>,
+    C: Comparator<K> + Comparator<Q>,
+{
+}
+
+/// A weakly consistent iterator over the entries of a [`SkipMultiMap`], in
+/// key order and then in insertion order.
+pub struct MultiIter<'a, K, V, C>(Iter<'a, MultiKey<K>, V, MultiCmp<C>>)
+where
+    C: Comparator<K>;
+
+impl<'a, K, V, C> Iterator for MultiIter<'a, K, V, C>
+where
+    C: Comparator<K>,
+{
+    type Item = EntryRef<'a, K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|entry| entry.map_key(|key| &key.key))
+    }
+}
+
+impl<K, V, C> FusedIterator for MultiIter<'_, K, V, C> where C: Comparator<K> {}
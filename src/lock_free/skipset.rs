@@ -0,0 +1,105 @@
+use std::borrow::Borrow;
+use std::ops::RangeBounds;
+
+use super::skiplist::{Iter, Range, SkipList};
+use crate::{Comparator, OrdComparator};
+
+/// A lock-free ordered set, kept as a [`SkipList`] whose values are all
+/// `()`.
+///
+/// Iterating yields [`EntryRef`](super::EntryRef)s rather than plain
+/// references, since a key another thread removes is only kept alive while
+/// something refers to it; read the key with `key()`.
+pub struct SkipSet<K, C = OrdComparator>
+where
+    C: Comparator<K>,
+{
+    list: SkipList<K, (), C>,
+}
+
+impl<K> SkipSet<K>
+where
+    K: Ord,
+{
+    pub fn new() -> Self {
+        SkipSet::with_cmp(OrdComparator)
+    }
+}
+
+impl<K> Default for SkipSet<K>
+where
+    K: Ord,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, C> SkipSet<K, C>
+where
+    C: Comparator<K>,
+{
+    pub fn with_cmp(cmp: C) -> Self {
+        SkipSet {
+            list: SkipList::with_cmp(cmp),
+        }
+    }
+}
+
+impl<K, C> SkipSet<K, C>
+where
+    K: Send + 'static,
+    C: Comparator<K>,
+{
+    /// Adds `key` to the set. Returns whether it was absent.
+    pub fn insert(&self, key: K) -> bool {
+        self.list.add(key, ())
+    }
+
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        self.list.contains_key(key)
+    }
+
+    /// Removes `key` from the set. Returns whether it was present.
+    pub fn remove<Q>(&self, key: &Q) -> bool
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        self.list.remove(key).is_some()
+    }
+
+    /// Returns the number of keys, with the same caveat under concurrent
+    /// writes as [`SkipList::len`].
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    /// Returns a weakly consistent iterator over the keys, in order. See
+    /// [`Iter`] for which concurrent changes it observes.
+    pub fn iter(&self) -> Iter<'_, K, (), C> {
+        self.list.iter()
+    }
+
+    /// Returns a weakly consistent iterator over the keys that lie in
+    /// `range`, in order.
+    pub fn range<Q, R>(&self, range: R) -> Range<'_, Q, R, K, (), C>
+    where
+        Q: ?Sized,
+        R: RangeBounds<Q>,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        self.list.range(range)
+    }
+}
@@ -0,0 +1,489 @@
+use std::thread;
+
+use super::*;
+use crate::ordered_map::tests::{
+    test_concurrent_get_or_insert, test_concurrent_insert, test_concurrent_ordered_map,
+    test_ordered_map, test_ordered_map_config, test_ordered_map_drop, test_ordered_map_navigate,
+    test_ordered_map_range, test_ordered_map_unit_drop, test_ordered_map_with_cmp, Navigate,
+};
+
+#[test]
+fn test_lockfree_ordered_map() {
+    test_ordered_map(SkipList::new());
+}
+
+#[test]
+fn test_lockfree_ordered_map_drop() {
+    test_ordered_map_drop(SkipList::new);
+}
+
+#[test]
+fn test_lockfree_ordered_map_unit_drop() {
+    test_ordered_map_unit_drop(SkipList::new);
+}
+
+#[test]
+fn test_lockfree_ordered_map_with_cmp() {
+    test_ordered_map_with_cmp(SkipList::with_cmp);
+}
+
+#[test]
+fn test_lockfree_ordered_map_config() {
+    test_ordered_map_config(SkipList::with_config);
+}
+
+#[test]
+fn test_lockfree_ordered_map_range() {
+    test_ordered_map_range(SkipList::new(), |map, range| {
+        map.range(range).map(|entry| *entry.key()).collect()
+    });
+}
+
+#[test]
+fn test_lockfree_ordered_map_navigate() {
+    test_ordered_map_navigate(SkipList::new(), |map, query| {
+        let entry = match query {
+            Navigate::Floor(key) => map.floor(&key),
+            Navigate::Ceiling(key) => map.ceiling(&key),
+            Navigate::Lower(key) => map.lower(&key),
+            Navigate::Higher(key) => map.higher(&key),
+            Navigate::LowerBound(key) => map.lower_bound(&key),
+            Navigate::UpperBound(key) => map.upper_bound(&key),
+            Navigate::First => map.first(),
+            Navigate::Last => map.last(),
+        };
+
+        entry.map(|entry| (*entry.key(), *entry))
+    });
+}
+
+#[test]
+fn test_lockfree_borrowed_keys() {
+    let strings = SkipList::new();
+    assert!(strings.add("apple".to_string(), 1));
+    assert_eq!(strings.get("apple").as_deref(), Some(&1));
+    assert_eq!(strings.remove("apple").as_deref(), Some(&1));
+    assert!(!strings.contains_key("apple"));
+}
+
+#[test]
+fn test_lockfree_concurrent_ordered_map() {
+    test_concurrent_ordered_map(&SkipList::new());
+}
+
+#[test]
+fn test_lockfree_concurrent_insert() {
+    test_concurrent_insert(&SkipList::new());
+}
+
+#[test]
+fn test_lockfree_concurrent_get_or_insert() {
+    test_concurrent_get_or_insert(&SkipList::new(), |map, key, f| {
+        *map.get_or_insert_with(key, f)
+    });
+}
+
+#[test]
+fn test_lockfree_iter() {
+    let length = 10000;
+
+    let skiplist = SkipList::new();
+
+    for i in (0..length).rev() {
+        skiplist.add(i, i);
+    }
+
+    assert!(skiplist.iter().map(|entry| *entry.key()).eq(0..length));
+
+    // Even keys stay put while writers churn the odd ones, so every scan must
+    // see all of them, in increasing order and each only once.
+    thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| {
+                for _ in 0..10 {
+                    for i in (1..length).step_by(2) {
+                        skiplist.remove(&i);
+                    }
+
+                    for i in (1..length).step_by(2) {
+                        skiplist.add(i, i);
+                    }
+                }
+            });
+        }
+
+        for _ in 0..4 {
+            s.spawn(|| {
+                for _ in 0..20 {
+                    let keys = skiplist
+                        .iter()
+                        .map(|entry| *entry.key())
+                        .collect::<Vec<_>>();
+
+                    assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+                    assert!(keys
+                        .iter()
+                        .filter(|key| *key % 2 == 0)
+                        .copied()
+                        .eq((0..length).step_by(2)));
+                }
+            });
+        }
+    });
+
+    assert_eq!(skiplist.len(), length);
+
+    let mut iter = skiplist.iter();
+    while iter.next().is_some() {}
+    skiplist.add(length, length);
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn test_lockfree_compare_and_swap() {
+    let skiplist = SkipList::new();
+
+    assert!(skiplist.replace(&1, 10).is_none());
+    assert!(!skiplist.contains_key(&1));
+
+    let err = skiplist.compare_and_swap(&1, &10, 11).err().unwrap();
+    assert!(err.current.is_none());
+    assert_eq!(err.new, 11);
+
+    skiplist.add(1, 10);
+    assert_eq!(skiplist.replace(&1, 20).as_deref(), Some(&10));
+    assert_eq!(skiplist.get(&1).as_deref(), Some(&20));
+
+    let err = skiplist.compare_and_swap(&1, &10, 11).err().unwrap();
+    assert_eq!(err.current.as_deref(), Some(&20));
+    assert_eq!(err.new, 11);
+
+    assert_eq!(
+        skiplist.compare_and_swap(&1, &20, 21).ok().as_deref(),
+        Some(&20)
+    );
+    assert_eq!(skiplist.get(&1).as_deref(), Some(&21));
+    assert_eq!(skiplist.len(), 1);
+
+    // Every increment is a read followed by a compare-and-swap, so none of
+    // them is lost only if no two swaps from the same value both succeed.
+    let threads = 8;
+    let rounds = 10000;
+
+    let counters = SkipList::new();
+
+    for key in 0..4 {
+        counters.add(key, 0);
+    }
+
+    thread::scope(|s| {
+        for t in 0..threads {
+            let counters = &counters;
+
+            s.spawn(move || {
+                for i in 0..rounds {
+                    let key = (t + i) % 4;
+
+                    loop {
+                        let current = *counters.get(&key).unwrap();
+
+                        if counters
+                            .compare_and_swap(&key, &current, current + 1)
+                            .is_ok()
+                        {
+                            break;
+                        }
+                    }
+                }
+            });
+        }
+    });
+
+    let total: usize = counters.iter().map(|entry| *entry.value()).sum();
+    assert_eq!(total, threads * rounds);
+}
+
+#[test]
+fn test_lockfree_pop() {
+    let skiplist = SkipList::new();
+
+    assert!(skiplist.peek_first().is_none());
+    assert!(skiplist.pop_last().is_none());
+
+    for i in [3, 1, 4, 5, 2] {
+        skiplist.add(i, i * 10);
+    }
+
+    assert_eq!(skiplist.peek_first().map(|entry| *entry.key()), Some(1));
+    assert_eq!(skiplist.peek_last().map(|entry| *entry.key()), Some(5));
+    assert_eq!(skiplist.pop_first().as_deref(), Some(&10));
+    assert_eq!(skiplist.pop_last().as_deref(), Some(&50));
+    assert_eq!(skiplist.len(), 3);
+    assert!(skiplist.iter().map(|entry| *entry.key()).eq(2..5));
+
+    // Popping threads race from both ends; every entry must come out exactly
+    // once, and in order within each thread since nothing is added meanwhile.
+    let length = 20000;
+
+    for i in 0..length {
+        skiplist.add(i, i);
+    }
+
+    let mut popped = thread::scope(|s| {
+        let handles = (0..8)
+            .map(|t| {
+                let skiplist = &skiplist;
+
+                s.spawn(move || {
+                    let mut popped = Vec::new();
+
+                    loop {
+                        let entry = if t % 2 == 0 {
+                            skiplist.pop_first()
+                        } else {
+                            skiplist.pop_last()
+                        };
+
+                        let Some(entry) = entry else {
+                            break;
+                        };
+
+                        popped.push(*entry.key());
+                    }
+
+                    if t % 2 == 0 {
+                        assert!(popped.windows(2).all(|pair| pair[0] < pair[1]));
+                    } else {
+                        assert!(popped.windows(2).all(|pair| pair[0] > pair[1]));
+                    }
+
+                    popped
+                })
+            })
+            .collect::<Vec<_>>();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+
+    popped.sort();
+    assert!(popped.into_iter().eq(0..length));
+    assert!(skiplist.is_empty());
+    assert!(skiplist.peek_last().is_none());
+}
+
+#[test]
+fn test_lockfree_pop_against_insert() {
+    let skiplist = SkipList::new();
+    let keys = 64;
+    let rounds = 2000;
+
+    // Every value is unique, and must end up exactly once among the values
+    // replaced, the values popped and the values left in the list, even
+    // when a replace lands on an entry that is being popped.
+    let mut seen = thread::scope(|s| {
+        let handles = (0..4)
+            .map(|t| {
+                let skiplist = &skiplist;
+
+                s.spawn(move || {
+                    let mut seen = Vec::new();
+
+                    for i in 0..rounds {
+                        let entry = if t % 2 == 0 {
+                            skiplist.insert(i % keys, t * rounds + i)
+                        } else {
+                            skiplist.pop_first()
+                        };
+
+                        seen.extend(entry.map(|entry| *entry));
+                    }
+
+                    seen
+                })
+            })
+            .collect::<Vec<_>>();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+
+    seen.extend(skiplist.iter().map(|entry| *entry));
+    seen.sort();
+
+    let inserted = (0..rounds).chain(2 * rounds..3 * rounds);
+    assert!(seen.into_iter().eq(inserted));
+}
+
+#[test]
+fn test_lockfree_skipset() {
+    let set = SkipSet::new();
+
+    // Each thread adds its own residue class, then drops every other key.
+    thread::scope(|s| {
+        for t in 0..4 {
+            let set = &set;
+
+            s.spawn(move || {
+                for i in (t..1000).step_by(4) {
+                    assert!(set.insert(i));
+                }
+
+                for i in (t..1000).step_by(8) {
+                    assert!(set.remove(&i));
+                }
+            });
+        }
+    });
+
+    let kept = (0..1000).filter(|i| i % 8 >= 4).collect::<Vec<_>>();
+
+    assert_eq!(set.len(), kept.len());
+    assert!(!set.insert(4));
+    assert!(set.contains(&4));
+    assert!(!set.contains(&3));
+    assert!(set
+        .iter()
+        .map(|entry| *entry.key())
+        .eq(kept.iter().copied()));
+    assert!(set
+        .range(..20)
+        .map(|entry| *entry.key())
+        .eq([4, 5, 6, 7, 12, 13, 14, 15]));
+}
+
+#[test]
+fn test_lockfree_multimap() {
+    let events = SkipMultiMap::new();
+
+    for (time, event) in [(2, "b"), (1, "a"), (2, "c"), (3, "d"), (2, "e")] {
+        events.insert(time, event);
+    }
+
+    assert_eq!(events.len(), 5);
+    assert!(events.get_all(&2).map(|entry| *entry).eq(["b", "c", "e"]));
+    assert!(events.get_all(&4).next().is_none());
+    assert!(events.iter().map(|entry| (*entry.key(), *entry)).eq([
+        (1, "a"),
+        (2, "b"),
+        (2, "c"),
+        (2, "e"),
+        (3, "d")
+    ]));
+
+    let removed = events.remove_one(&2).unwrap();
+    assert_eq!((*removed.key(), *removed), (2, "b"));
+    drop(removed);
+    assert!(events.get_all(&2).map(|entry| *entry).eq(["c", "e"]));
+    assert_eq!(events.remove_all(&2), 2);
+    assert!(events.remove_one(&2).is_none());
+    assert_eq!(events.len(), 2);
+
+    // Threads append to and drain shared keys; every value must be removed
+    // exactly once, and each thread's values must stay in its order.
+    let threads = 4;
+    let rounds = 5000;
+
+    let multimap = SkipMultiMap::new();
+
+    let mut removed = thread::scope(|s| {
+        let handles = (0..threads)
+            .map(|t| {
+                let multimap = &multimap;
+
+                s.spawn(move || {
+                    let mut removed = Vec::new();
+
+                    for i in 0..rounds {
+                        multimap.insert(i % 4, (t, i));
+
+                        if i % 2 == 1 {
+                            removed.extend(multimap.remove_one(&(i % 4)).map(|entry| *entry));
+                        }
+                    }
+
+                    removed
+                })
+            })
+            .collect::<Vec<_>>();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+
+    for key in 0..4 {
+        let values = multimap
+            .get_all(&key)
+            .map(|entry| *entry)
+            .collect::<Vec<_>>();
+
+        for t in 0..threads {
+            let own = values.iter().filter(|(thread, _)| *thread == t);
+            assert!(own.clone().zip(own.skip(1)).all(|(a, b)| a.1 < b.1));
+        }
+
+        assert_eq!(multimap.remove_all(&key), values.len());
+        removed.extend(values);
+    }
+
+    removed.sort();
+    assert!(removed
+        .into_iter()
+        .eq((0..threads).flat_map(|t| (0..rounds).map(move |i| (t, i)))));
+    assert!(multimap.is_empty());
+}
+
+#[test]
+fn test_lockfree_navigate() {
+    let length = 2000;
+
+    let skiplist = SkipList::new();
+
+    for i in 0..length {
+        skiplist.add(i, i);
+    }
+
+    // Even keys stay put while writers churn the odd ones, so each query has
+    // at most two right answers: the odd neighbour or the even one past it.
+    thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| {
+                for _ in 0..10 {
+                    for i in (1..length).step_by(2) {
+                        skiplist.remove(&i);
+                    }
+
+                    for i in (1..length).step_by(2) {
+                        skiplist.add(i, i);
+                    }
+                }
+            });
+        }
+
+        for _ in 0..4 {
+            s.spawn(|| {
+                for _ in 0..10 {
+                    for i in (2..length - 2).step_by(2) {
+                        let key = |entry: Option<EntryRef<'_, usize, usize>>| *entry.unwrap().key();
+
+                        assert_eq!(key(skiplist.floor(&i)), i);
+                        assert_eq!(key(skiplist.ceiling(&i)), i);
+                        assert!([i - 1, i - 2].contains(&key(skiplist.lower(&i))));
+                        assert!([i + 1, i + 2].contains(&key(skiplist.higher(&i))));
+                        assert!([i + 1, i + 2].contains(&key(skiplist.ceiling(&(i + 1)))));
+                        assert!([i + 1, i].contains(&key(skiplist.floor(&(i + 1)))));
+                    }
+
+                    assert_eq!(skiplist.first().map(|entry| *entry.key()), Some(0));
+                    assert!([length - 1, length - 2].contains(skiplist.last().unwrap().key()));
+                }
+            });
+        }
+    });
+}
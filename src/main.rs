@@ -1,35 +1,49 @@
-#![feature(ptr_mask)]
-#![feature(strict_provenance)]
-#![feature(core_intrinsics)]
+use std::hint::black_box;
+use std::time::{Duration, Instant};
 
-use lock_base::test_lockbase_skiplist;
-use lock_free::test_lockfree_skiplist;
-use sequencial::test_sequencial_skiplist;
+use rand::seq::SliceRandom;
+use skiplist::OrderedMap;
+use skiplist::{lock_base, lock_free, sequencial};
 
-mod lock_base;
-mod lock_free;
-mod sequencial;
+const BENCH_KEYS: usize = 1_000_000;
 
-fn measure_time<F: Fn()>(f: F) {
-    let start = std::time::Instant::now();
+/// Prints how many inserts, then lookups, of `BENCH_KEYS` keys in random
+/// order `map` does per second.
+fn bench_ordered_map<M: OrderedMap<usize, usize>>(name: &str, mut map: M) {
+    let mut keys: Vec<usize> = (0..BENCH_KEYS).collect();
+    keys.shuffle(&mut rand::thread_rng());
 
-    f();
+    let start = Instant::now();
 
-    let end = std::time::Instant::now();
+    for &key in &keys {
+        map.add(key, key);
+    }
 
-    println!("time: {:?}", end - start);
-}
+    let insert = start.elapsed();
 
-pub fn main() {
-    measure_time(|| {
-        test_sequencial_skiplist();
-    });
+    keys.shuffle(&mut rand::thread_rng());
+
+    let start = Instant::now();
 
-    measure_time(|| {
-        test_lockbase_skiplist();
-    });
+    for &key in &keys {
+        black_box(map.contains_key(&key));
+    }
 
-    measure_time(|| {
-        test_lockfree_skiplist();
-    });
+    let lookup = start.elapsed();
+
+    println!(
+        "{name}: insert {:.2} Mops/s, lookup {:.2} Mops/s",
+        mops(insert),
+        mops(lookup)
+    );
+}
+
+fn mops(elapsed: Duration) -> f64 {
+    BENCH_KEYS as f64 / elapsed.as_secs_f64() / 1e6
+}
+
+pub fn main() {
+    bench_ordered_map("sequencial", sequencial::SkipList::new());
+    bench_ordered_map("lock_base", lock_base::SkipList::new());
+    bench_ordered_map("lock_free", lock_free::SkipList::new());
 }
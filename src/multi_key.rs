@@ -0,0 +1,60 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+
+use crate::Comparator;
+
+/// The key under which a multimap stores a value: the user's key plus a
+/// sequence number taken when the value was inserted.
+///
+/// Equal keys are told apart by their sequence numbers, so every stored key
+/// is unique and the lists need no special handling for duplicates, while
+/// values for the same key sort in insertion order.
+pub(crate) struct MultiKey<K> {
+    pub key: K,
+    pub seq: u64,
+}
+
+/// A key looked up on its own, which compares equal to the [`MultiKey`]s of
+/// every value stored for it.
+#[repr(transparent)]
+pub(crate) struct KeyOnly<Q: ?Sized>(Q);
+
+impl<Q: ?Sized> KeyOnly<Q> {
+    pub fn new(key: &Q) -> &KeyOnly<Q> {
+        // `KeyOnly` is a transparent wrapper, so the layouts match.
+        unsafe { &*(key as *const Q as *const KeyOnly<Q>) }
+    }
+}
+
+impl<K, Q> Borrow<KeyOnly<Q>> for MultiKey<K>
+where
+    Q: ?Sized,
+    K: Borrow<Q>,
+{
+    fn borrow(&self) -> &KeyOnly<Q> {
+        KeyOnly::new(self.key.borrow())
+    }
+}
+
+/// Orders [`MultiKey`]s by key with the user's comparator, then by sequence
+/// number.
+pub(crate) struct MultiCmp<C>(pub C);
+
+impl<K, C> Comparator<MultiKey<K>> for MultiCmp<C>
+where
+    C: Comparator<K>,
+{
+    fn compare(&self, k1: &MultiKey<K>, k2: &MultiKey<K>) -> Ordering {
+        self.0.compare(&k1.key, &k2.key).then(k1.seq.cmp(&k2.seq))
+    }
+}
+
+impl<Q, C> Comparator<KeyOnly<Q>> for MultiCmp<C>
+where
+    Q: ?Sized,
+    C: Comparator<Q>,
+{
+    fn compare(&self, k1: &KeyOnly<Q>, k2: &KeyOnly<Q>) -> Ordering {
+        self.0.compare(&k1.0, &k2.0)
+    }
+}
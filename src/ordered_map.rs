@@ -0,0 +1,66 @@
+use std::borrow::Borrow;
+use std::ops::Deref;
+
+#[cfg(test)]
+pub(crate) mod tests;
+
+/// An ordered map from `K` to `V`.
+///
+/// Implemented by every skiplist in this crate, so the same code (and the
+/// same tests) can run against any of them.
+pub trait OrderedMap<K, V> {
+    /// What lookups hand out: a plain reference, or a guard for maps that
+    /// reclaim removed entries while other threads may still read them.
+    type Ref<'a>: Deref<Target = V>
+    where
+        Self: 'a;
+
+    /// Inserts `key` with `value`, returning `false` without touching the
+    /// map if `key` is already present.
+    fn add(&mut self, key: K, value: V) -> bool;
+
+    /// Inserts `key` with `value` unless `key` is present, in which case
+    /// both are handed back.
+    fn try_insert(&mut self, key: K, value: V) -> Result<(), (K, V)>;
+
+    /// Inserts `key` with `value`, replacing and returning the value already
+    /// held for `key`, if any.
+    fn insert(&mut self, key: K, value: V) -> Option<V>;
+
+    /// Removes `key`, returning its value if it was present.
+    fn remove(&mut self, key: &K) -> Option<V>;
+
+    fn get(&self, key: &K) -> Option<Self::Ref<'_>>;
+
+    fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns the number of entries. Concurrent maps document how exact it
+    /// is while other threads are writing.
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// An [`OrderedMap`] that can be modified through a shared reference by many
+/// threads at once.
+pub trait ConcurrentOrderedMap<K, V>: OrderedMap<K, V> + Sync {
+    /// What [`remove_shared`](Self::remove_shared) hands out: the value
+    /// itself, or a guard keeping it readable if other threads may still be
+    /// looking at it.
+    type Removed<'a>: Borrow<V>
+    where
+        Self: 'a;
+
+    /// Same as [`OrderedMap::add`], but callable concurrently.
+    fn add_shared(&self, key: K, value: V) -> bool;
+
+    /// Same as [`OrderedMap::insert`], but callable concurrently.
+    fn insert_shared(&self, key: K, value: V) -> Option<Self::Removed<'_>>;
+
+    /// Same as [`OrderedMap::remove`], but callable concurrently.
+    fn remove_shared(&self, key: &K) -> Option<Self::Removed<'_>>;
+}
@@ -0,0 +1,583 @@
+use std::borrow::Borrow;
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::ops::{Bound, RangeBounds};
+use std::panic::{self, RefUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+use std::sync::Arc;
+use std::thread;
+
+use rand::{seq::SliceRandom, thread_rng, Rng};
+
+use super::{ConcurrentOrderedMap, OrderedMap};
+use crate::MAX_LEVEL;
+use crate::{LevelGenerator, OrdComparator, SeededGenerator, SkipListConfig, XorShiftGenerator};
+
+pub(crate) fn test_ordered_map<M: OrderedMap<usize, usize>>(mut map: M) {
+    let mut rng = thread_rng();
+
+    let length = 50000;
+
+    let mut numbers = (1..length).collect::<Vec<_>>();
+
+    numbers.shuffle(&mut rng);
+
+    assert!(map.is_empty());
+
+    for i in numbers.iter() {
+        assert!(map.add(*i, *i));
+    }
+
+    for i in numbers.iter() {
+        assert!(!map.add(*i, 0));
+        assert_eq!(map.try_insert(*i, 0), Err((*i, 0)));
+    }
+
+    assert_eq!(map.len(), length - 1);
+
+    for i in numbers.iter() {
+        assert_eq!(map.insert(*i, *i * 2), Some(*i));
+    }
+
+    assert_eq!(map.insert(0, 0), None);
+    assert_eq!(map.remove(&0), Some(0));
+    assert_eq!(map.try_insert(0, 0), Ok(()));
+    assert_eq!(map.remove(&0), Some(0));
+
+    for i in numbers.iter() {
+        assert_eq!(map.insert(*i, *i), Some(*i * 2));
+    }
+
+    assert_eq!(map.len(), length - 1);
+
+    for i in 1..length {
+        assert_eq!(map.get(&i).as_deref(), Some(&i));
+    }
+
+    assert!(!map.contains_key(&0));
+    assert!(!map.contains_key(&length));
+
+    numbers.shuffle(&mut rng);
+
+    for (removed, i) in numbers.iter().enumerate() {
+        assert_eq!(map.len(), length - 1 - removed);
+        assert_eq!(map.remove(i), Some(*i));
+    }
+
+    for i in numbers {
+        assert_eq!(map.remove(&i), None);
+        assert!(!map.contains_key(&i));
+    }
+
+    assert!(map.is_empty());
+}
+
+pub(crate) fn test_concurrent_ordered_map<M: ConcurrentOrderedMap<usize, usize>>(map: &M) {
+    thread::scope(|s| {
+        let handles = (1..32)
+            .map(|i| {
+                s.spawn(move || {
+                    let length = 50000;
+
+                    let mut numbers = (1..length).collect::<Vec<_>>();
+
+                    let rng = &mut thread_rng();
+
+                    numbers.shuffle(rng);
+
+                    for j in numbers.iter() {
+                        assert!(map.add_shared((i * length) + j, i * j));
+                    }
+
+                    for j in numbers.iter() {
+                        assert_eq!(map.get(&((i * length) + j)).as_deref(), Some(&(i * j)));
+                    }
+
+                    numbers.shuffle(rng);
+
+                    for j in numbers {
+                        let removed = map.remove_shared(&((i * length) + j));
+                        assert_eq!(removed.as_ref().map(Borrow::borrow), Some(&(i * j)));
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    });
+
+    assert!(map.is_empty());
+}
+
+/// Threads insert and remove the same few keys at once. Every value put in
+/// must come out exactly once: replaced by an insert, taken by a remove, or
+/// left in the map at the end.
+pub(crate) fn test_concurrent_insert<M: ConcurrentOrderedMap<usize, usize>>(map: &M) {
+    let keys = 16;
+    let rounds = 20000;
+    let threads = 8;
+
+    let taken = thread::scope(|s| {
+        let handles = (0..threads)
+            .map(|i| {
+                s.spawn(move || {
+                    let mut taken = Vec::new();
+
+                    for j in 0..rounds {
+                        let key = (i + j) % keys;
+                        let value = i * rounds + j;
+
+                        if let Some(old) = map.insert_shared(key, value) {
+                            taken.push(*old.borrow());
+                        }
+
+                        if j % 3 == 0 {
+                            if let Some(old) = map.remove_shared(&((key + 1) % keys)) {
+                                taken.push(*old.borrow());
+                            }
+                        }
+                    }
+
+                    taken
+                })
+            })
+            .collect::<Vec<_>>();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+
+    let left = (0..keys).filter_map(|key| map.get(&key).as_deref().copied());
+
+    let mut values = taken.into_iter().chain(left).collect::<Vec<_>>();
+    values.sort();
+
+    assert!(values.into_iter().eq(0..threads * rounds));
+}
+
+/// `get_or_insert_with` returns the value of a key after inserting the result
+/// of the given function if it was absent.
+pub(crate) fn test_concurrent_get_or_insert<M, F>(map: &M, get_or_insert_with: F)
+where
+    M: ConcurrentOrderedMap<usize, usize>,
+    F: Fn(&M, usize, &dyn Fn() -> usize) -> usize + Sync,
+{
+    let length = 1000;
+    let calls = AtomicUsize::new(0);
+
+    let seen = thread::scope(|s| {
+        let handles = (0..8)
+            .map(|i| {
+                let (calls, get_or_insert_with) = (&calls, &get_or_insert_with);
+
+                s.spawn(move || {
+                    let mut numbers = (0..length).collect::<Vec<_>>();
+                    numbers.shuffle(&mut thread_rng());
+                    numbers.sort_by_key(|j| (j + i) % 4);
+
+                    let mut seen = vec![0; length];
+
+                    for j in numbers {
+                        seen[j] = get_or_insert_with(map, j, &|| {
+                            calls.fetch_add(1, Relaxed);
+                            i * length + j
+                        });
+                    }
+
+                    seen
+                })
+            })
+            .collect::<Vec<_>>();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+
+    // Every thread got the value that won, whichever thread made it.
+    for j in 0..length {
+        let winner = map.get(&j).as_deref().copied();
+        assert_eq!(winner.map(|value| value % length), Some(j));
+        assert!(seen.iter().all(|seen| Some(seen[j]) == winner));
+    }
+
+    assert_eq!(map.len(), length);
+    assert!(calls.load(Relaxed) >= length);
+
+    // A present key leaves the function alone.
+    assert_eq!(get_or_insert_with(map, 0, &|| unreachable!()), seen[0][0]);
+}
+
+/// `new` builds a map ordered by the given comparator.
+pub(crate) fn test_ordered_map_with_cmp<M, F>(new: F)
+where
+    M: OrderedMap<String, usize>,
+    F: Fn(fn(&String, &String) -> Ordering) -> M,
+{
+    let mut map = new(|k1, k2| k1.to_lowercase().cmp(&k2.to_lowercase()));
+
+    assert!(map.add("Apple".to_string(), 1));
+    assert!(map.add("banana".to_string(), 2));
+    assert!(!map.add("APPLE".to_string(), 3));
+
+    assert_eq!(map.get(&"apple".to_string()).as_deref(), Some(&1));
+    assert_eq!(map.get(&"BaNaNa".to_string()).as_deref(), Some(&2));
+
+    assert_eq!(map.remove(&"aPPLE".to_string()), Some(1));
+    assert!(!map.contains_key(&"Apple".to_string()));
+    assert!(map.contains_key(&"Banana".to_string()));
+}
+
+/// `with_config` builds a map with the given settings, and panics on ones
+/// out of bounds.
+pub(crate) fn test_ordered_map_config<M, F>(with_config: F)
+where
+    M: OrderedMap<usize, usize>,
+    F: Fn(SkipListConfig<OrdComparator, SeededGenerator>) -> M + RefUnwindSafe,
+{
+    let mut rng = thread_rng();
+    let length = 2000;
+
+    let seeded = || SkipListConfig::new().level_generator(SeededGenerator::new(thread_rng().gen()));
+
+    // A high `p` sends most entries to the top level, which must still fit.
+    for (p, max_level) in [(0.5, 1), (0.9, 2), (0.99, 4), (0.25, MAX_LEVEL)] {
+        let mut map = with_config(seeded().p(p).max_level(max_level));
+        let mut numbers = (0..length).collect::<Vec<_>>();
+
+        numbers.shuffle(&mut rng);
+
+        for i in numbers.iter() {
+            assert!(map.add(*i, *i));
+        }
+
+        assert_eq!(map.len(), length);
+
+        for i in 0..length {
+            assert_eq!(map.get(&i).as_deref(), Some(&i));
+        }
+
+        numbers.shuffle(&mut rng);
+
+        for i in numbers.iter() {
+            assert_eq!(map.remove(i), Some(*i));
+        }
+
+        assert!(map.is_empty());
+    }
+
+    let invalid = [
+        seeded().p(0.),
+        seeded().p(1.),
+        seeded().p(f32::NAN),
+        seeded().max_level(0),
+        seeded().max_level(MAX_LEVEL + 1),
+    ];
+
+    for config in invalid {
+        assert!(panic::catch_unwind(|| with_config(config)).is_err());
+    }
+
+    test_level_generator(&XorShiftGenerator);
+    test_level_generator(&SeededGenerator::new(rng.gen()));
+
+    // The same seed draws the same levels, so a list gets the same towers.
+    let seed = rng.gen();
+    let draw = |generator: SeededGenerator| {
+        (0..1000)
+            .map(|i| generator.random_level([0.5, 0.25][i % 2], MAX_LEVEL))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        draw(SeededGenerator::new(seed)),
+        draw(SeededGenerator::new(seed))
+    );
+    assert_ne!(
+        draw(SeededGenerator::new(seed)),
+        draw(SeededGenerator::new(seed ^ 1))
+    );
+}
+
+/// Levels drawn by `generator` stay below `max_level` and reach each level
+/// with probability `p` from the one below, so they average `p / (1 - p)`.
+fn test_level_generator(generator: &impl LevelGenerator) {
+    let draws = 100000;
+
+    for p in [0.5, 0.25, 0.75] {
+        let total: usize = (0..draws)
+            .map(|_| generator.random_level(p, MAX_LEVEL))
+            .sum();
+        let mean = total as f32 / draws as f32;
+        let expected = p / (1. - p);
+
+        assert!((mean - expected).abs() < expected * 0.05);
+    }
+
+    assert!((0..draws).all(|_| generator.random_level(0.9, 3) < 3));
+}
+
+/// Counts how many times it has been dropped.
+pub(crate) struct DropCounter(Arc<AtomicUsize>);
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Relaxed);
+    }
+}
+
+/// A key ordered by its number alone that counts how many times it has
+/// been dropped.
+pub(crate) struct DropKey(usize, Arc<AtomicUsize>);
+
+impl PartialEq for DropKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for DropKey {}
+
+impl PartialOrd for DropKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DropKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl Drop for DropKey {
+    fn drop(&mut self) {
+        self.1.fetch_add(1, Relaxed);
+    }
+}
+
+/// Every key and value the map took is dropped exactly once: rejected and
+/// replaced ones right away, removed ones once `remove` returns, and the
+/// rest with the map.
+pub(crate) fn test_ordered_map_drop<M, F>(new: F)
+where
+    M: OrderedMap<DropKey, DropCounter>,
+    F: Fn() -> M,
+{
+    let key_drops = Arc::new(AtomicUsize::new(0));
+    let drops = Arc::new(AtomicUsize::new(0));
+    let length = 1000;
+
+    let key = |i| DropKey(i, key_drops.clone());
+    let value = || DropCounter(drops.clone());
+    let counts = || (key_drops.load(Relaxed), drops.load(Relaxed));
+
+    {
+        let mut map = new();
+
+        for i in 0..length {
+            assert!(map.add(key(i), value()));
+        }
+
+        // A rejected entry is dropped right away by `add`, and handed back
+        // by `try_insert`.
+        assert!(!map.add(key(0), value()));
+        assert_eq!(counts(), (1, 1));
+
+        let rejected = map.try_insert(key(0), value()).unwrap_err();
+        assert_eq!(counts(), (1, 1));
+        drop(rejected);
+        assert_eq!(counts(), (2, 2));
+
+        // Replacing keeps one key and drops the other.
+        let replaced = map.insert(key(0), value());
+        assert_eq!(counts(), (3, 2));
+        drop(replaced);
+        assert_eq!(counts(), (3, 3));
+
+        // Looked up with keys of their own, which are not counted.
+        let probes = Arc::new(AtomicUsize::new(0));
+
+        for i in 0..length / 2 {
+            drop(map.remove(&DropKey(i, probes.clone())));
+        }
+
+        assert_eq!(counts(), (3 + length / 2, 3 + length / 2));
+    }
+
+    assert_eq!(counts(), (3 + length, 3 + length));
+
+    // An empty map only owns its sentinels.
+    drop(new());
+    assert_eq!(counts(), (3 + length, 3 + length));
+}
+
+thread_local! {
+    static UNIT_DROPS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// A zero-sized value that counts its drops on the current thread, so
+/// entries have to store it even though it takes no room.
+pub(crate) struct DropUnit;
+
+impl Drop for DropUnit {
+    fn drop(&mut self) {
+        UNIT_DROPS.with(|drops| drops.set(drops.get() + 1));
+    }
+}
+
+pub(crate) fn test_ordered_map_unit_drop<M, F>(new: F)
+where
+    M: OrderedMap<usize, DropUnit>,
+    F: Fn() -> M,
+{
+    let drops = || UNIT_DROPS.with(Cell::get);
+    let length = 1000;
+
+    {
+        let mut map = new();
+
+        for i in 0..length {
+            assert!(map.add(i, DropUnit));
+        }
+
+        assert!(!map.add(0, DropUnit));
+        assert_eq!(drops(), 1);
+
+        drop(map.insert(0, DropUnit));
+        assert_eq!(drops(), 2);
+
+        for i in 0..length / 2 {
+            drop(map.remove(&i));
+        }
+
+        assert_eq!(drops(), 2 + length / 2);
+    }
+
+    assert_eq!(drops(), 2 + length);
+}
+
+/// `range` collects the keys of the map within the given bounds, in the
+/// order its range iterator yields them.
+pub(crate) fn test_ordered_map_range<M, F>(mut map: M, range: F)
+where
+    M: OrderedMap<usize, usize>,
+    F: Fn(&M, (Bound<usize>, Bound<usize>)) -> Vec<usize>,
+{
+    use Bound::*;
+
+    let length = 200;
+
+    let mut numbers = (0..length).step_by(2).collect::<Vec<_>>();
+    numbers.shuffle(&mut thread_rng());
+
+    for i in numbers.iter() {
+        assert!(map.add(*i, *i));
+    }
+
+    let bounds = [
+        Unbounded,
+        Included(0),
+        Excluded(0),
+        Included(51),
+        Excluded(52),
+    ];
+    let ends = [
+        Unbounded,
+        Included(98),
+        Excluded(98),
+        Included(99),
+        Excluded(length),
+    ];
+
+    for start in bounds {
+        for end in ends {
+            let expected = (0..length)
+                .step_by(2)
+                .filter(|key| (start, end).contains(key))
+                .collect::<Vec<_>>();
+
+            assert_eq!(range(&map, (start, end)), expected);
+        }
+    }
+
+    // A start past the end gives nothing.
+    assert!(range(&map, (Included(60), Excluded(40))).is_empty());
+    assert!(range(&map, (Excluded(50), Excluded(50))).is_empty());
+    assert!(range(&map, (Included(length), Unbounded)).is_empty());
+}
+
+/// A navigational query, answered by each list's own method of that name.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Navigate {
+    Floor(usize),
+    Ceiling(usize),
+    Lower(usize),
+    Higher(usize),
+    LowerBound(usize),
+    UpperBound(usize),
+    First,
+    Last,
+}
+
+pub(crate) fn test_ordered_map_navigate<M, F>(mut map: M, navigate: F)
+where
+    M: OrderedMap<usize, usize>,
+    F: Fn(&M, Navigate) -> Option<(usize, usize)>,
+{
+    use Navigate::*;
+
+    assert_eq!(navigate(&map, First), None);
+    assert_eq!(navigate(&map, Last), None);
+    assert_eq!(navigate(&map, Floor(10)), None);
+    assert_eq!(navigate(&map, Ceiling(10)), None);
+
+    let length = 200;
+
+    let mut numbers = (0..length).step_by(2).collect::<Vec<_>>();
+    numbers.shuffle(&mut thread_rng());
+
+    for i in numbers.iter() {
+        assert!(map.add(*i, *i * 10));
+    }
+
+    let keys = (0..length).step_by(2).collect::<Vec<_>>();
+    let entry = |key: Option<&usize>| key.map(|key| (*key, *key * 10));
+
+    for key in [0, 1, 2, 51, 52, length - 2, length - 1, length + 50] {
+        let below = keys.iter().filter(|k| **k < key);
+        let above = keys.iter().filter(|k| **k > key);
+        let at = keys.iter().find(|k| **k == key);
+
+        assert_eq!(
+            navigate(&map, Floor(key)),
+            entry(at.or(below.clone().next_back()))
+        );
+        assert_eq!(
+            navigate(&map, Ceiling(key)),
+            entry(at.or(above.clone().next()))
+        );
+        assert_eq!(navigate(&map, Lower(key)), entry(below.clone().next_back()));
+        assert_eq!(navigate(&map, Higher(key)), entry(above.clone().min()));
+        assert_eq!(
+            navigate(&map, LowerBound(key)),
+            entry(at.or(above.clone().next()))
+        );
+        assert_eq!(navigate(&map, UpperBound(key)), entry(above.min()));
+    }
+
+    assert_eq!(navigate(&map, First), Some((0, 0)));
+    assert_eq!(navigate(&map, Last), Some((length - 2, (length - 2) * 10)));
+
+    map.remove(&0);
+    map.remove(&(length - 2));
+    assert_eq!(navigate(&map, First), Some((2, 20)));
+    assert_eq!(navigate(&map, Last), Some((length - 4, (length - 4) * 10)));
+    assert_eq!(navigate(&map, Floor(1)), None);
+    assert_eq!(navigate(&map, Higher(length - 4)), None);
+}
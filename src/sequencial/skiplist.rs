@@ -1,24 +1,34 @@
-use std::{
-    cmp::{min, Ordering},
-    ptr::null_mut,
-};
+use std::ops::{Bound, RangeBounds};
+use std::{borrow::Borrow, ptr::null_mut};
 
 use self::entry::{Entry, Key};
-use rand::random;
+use crate::{Comparator, LevelGenerator, OrdComparator, OrderedMap, SkipListConfig};
 
+pub use self::cursor::CursorMut;
+pub use self::iter::{IntoIter, Iter, IterMut, Keys, Range, Values};
+pub use self::map_entry::{OccupiedEntry, VacantEntry};
+
+mod cursor;
 mod entry;
+mod iter;
+pub(super) mod map_entry;
 
-pub struct SkipList<K, V, C = fn(&K, &K) -> Ordering>
+pub struct SkipList<K, V, C = OrdComparator>
 where
-    K: Ord,
-    C: Fn(&K, &K) -> Ordering,
+    C: Comparator<K>,
 {
     head: *mut Entry<K, V, C>,
-    key_cmp: C,
-}
-
-fn default_cmp<K: Ord>(k1: &K, k2: &K) -> Ordering {
-    k1.cmp(k2)
+    tail: *mut Entry<K, V, C>,
+    pub(super) key_cmp: C,
+    len: usize,
+    p: f32,
+    level_generator: Box<dyn LevelGenerator>,
+    /// How many levels the head has, which no entry exceeds.
+    pub(super) max_level: usize,
+    /// How many levels, counted from the bottom, any entry has reached.
+    /// Above them the head links straight to the tail, so searches start
+    /// here.
+    current_height: usize,
 }
 
 impl<K, V> SkipList<K, V>
@@ -26,152 +36,758 @@ where
     K: Ord,
 {
     pub fn new() -> Self {
-        SkipList::with_cmp(default_cmp)
+        SkipList::with_cmp(OrdComparator)
     }
 }
 
-impl<K, V, C> SkipList<K, V, C>
+impl<K, V> Default for SkipList<K, V>
 where
     K: Ord,
-    C: Fn(&K, &K) -> Ordering,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, C> SkipList<K, V, C>
+where
+    C: Comparator<K>,
 {
     pub fn with_cmp(cmp: C) -> Self {
-        let head = Box::into_raw(Box::new(Entry {
-            key: Key::Head,
-            value: None,
-            nexts: vec![null_mut(); MAX_LEVEL],
-        }));
-
-        let tail = Box::into_raw(Box::new(Entry {
-            key: Key::Tail,
-            value: None,
-            nexts: vec![null_mut(); MAX_LEVEL],
-        }));
+        SkipList::with_config(SkipListConfig::new().cmp(cmp))
+    }
+
+    /// Creates a list with the promotion probability, number of levels,
+    /// comparator and level generator of `config`.
+    ///
+    /// # Panics
+    ///
+    /// If `config` holds a `p` or `max_level` out of bounds.
+    pub fn with_config<G>(config: SkipListConfig<C, G>) -> Self
+    where
+        G: LevelGenerator + 'static,
+    {
+        config.validate();
+
+        let max_level = config.max_level;
+        let head = Entry::alloc(Key::Head, None, max_level, 1);
+        let tail = Entry::alloc(Key::Tail, None, max_level, 0);
 
         unsafe {
-            for level in 0..MAX_LEVEL {
-                (*head).nexts[level] = tail;
+            let head = &mut *head;
+
+            for level in 0..max_level {
+                head.nexts_mut()[level] = tail;
             }
         }
 
-        SkipList { head, key_cmp: cmp }
+        SkipList {
+            head,
+            tail,
+            key_cmp: config.cmp,
+            len: 0,
+            p: config.p,
+            level_generator: Box::new(config.level_generator),
+            max_level,
+            current_height: 1,
+        }
     }
 
     pub fn add(&mut self, key: K, value: V) -> bool {
-        let top_level = random_level();
-        let mut preds = vec![null_mut(); MAX_LEVEL + 1];
-        let mut succs = vec![null_mut(); MAX_LEVEL + 1];
+        self.try_insert(key, value).is_ok()
+    }
 
-        let key = Key::Entry(key);
+    /// Inserts `key` with `value` unless it is present, in which case both
+    /// are handed back untouched.
+    pub fn try_insert(&mut self, key: K, value: V) -> Result<(), (K, V)> {
+        let mut preds = vec![null_mut(); self.max_level];
+        let mut succs = vec![null_mut(); self.max_level];
 
         let level_found = self.find(&key, &mut preds, &mut succs);
 
         if level_found.is_some() {
-            return false;
+            return Err((key, value));
         }
 
-        let new_entry = Box::into_raw(Box::new(Entry {
-            key,
-            value: Some(value),
-            nexts: vec![null_mut(); top_level + 1],
-        }));
+        self.link(key, value, &preds, &succs);
+
+        Ok(())
+    }
+
+    /// Inserts `key` with `value`, replacing and returning the value already
+    /// held for `key`, if any. The key already in the list is kept.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.entry(key) {
+            map_entry::Entry::Occupied(mut entry) => Some(entry.insert(value)),
+            map_entry::Entry::Vacant(entry) => {
+                entry.insert(value);
+                None
+            }
+        }
+    }
+
+    /// Gets the entry for `key`, to look at or change it in place with a
+    /// single search.
+    pub fn entry(&mut self, key: K) -> map_entry::Entry<'_, K, V, C> {
+        let mut preds = vec![null_mut(); self.max_level];
+        let mut succs = vec![null_mut(); self.max_level];
+
+        match self.find(&key, &mut preds, &mut succs) {
+            Some(level_found) => map_entry::Entry::Occupied(OccupiedEntry {
+                entry: succs[level_found],
+                level_found,
+                preds,
+                list: self,
+            }),
+            None => map_entry::Entry::Vacant(VacantEntry {
+                key,
+                preds,
+                succs,
+                list: self,
+            }),
+        }
+    }
+
+    /// Returns a cursor at the first entry, or at the end if the list is
+    /// empty.
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, K, V, C> {
+        let current = unsafe { (*self.head).nexts()[0] };
+        let preds = vec![self.head; self.max_level];
+        let positions = vec![0; self.max_level];
+
+        CursorMut::new(self, current, preds, positions)
+    }
+
+    /// Returns a cursor at the first entry whose key is not less than `key`,
+    /// or at the end if there is none.
+    pub fn cursor_mut_at<Q>(&mut self, key: &Q) -> CursorMut<'_, K, V, C>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        let mut preds = vec![null_mut(); self.max_level];
+        let mut succs = vec![null_mut(); self.max_level];
+
+        self.find(key, &mut preds, &mut succs);
+        let positions = unsafe { self.positions(&preds) };
+
+        CursorMut::new(self, succs[0], preds, positions)
+    }
+
+    /// Links a new entry for `key` between the `preds` and `succs` found by a
+    /// search for it.
+    fn link(
+        &mut self,
+        key: K,
+        value: V,
+        preds: &[*mut Entry<K, V, C>],
+        succs: &[*mut Entry<K, V, C>],
+    ) -> *mut Entry<K, V, C> {
+        let pred_positions = unsafe { self.positions(preds) };
+
+        self.link_at(key, value, preds, succs, &pred_positions)
+    }
+
+    /// Same as `link`, for a caller that already knows the bottom-level
+    /// positions of `preds`.
+    fn link_at(
+        &mut self,
+        key: K,
+        value: V,
+        preds: &[*mut Entry<K, V, C>],
+        succs: &[*mut Entry<K, V, C>],
+        pred_positions: &[usize],
+    ) -> *mut Entry<K, V, C> {
+        let top_level = self.level_generator.random_level(self.p, self.max_level);
+
+        let new_entry = Entry::alloc(Key::Entry(key), Some(value), top_level + 1, 0);
 
         unsafe {
+            let position = pred_positions[0] + 1;
+
             for level in 0..=top_level {
-                (*new_entry).nexts[level] = succs[level];
-                (*preds[level]).nexts[level] = new_entry;
+                let pred = &mut *preds[level];
+
+                (*new_entry).widths_mut()[level] =
+                    pred_positions[level] + pred.widths()[level] + 1 - position;
+                pred.widths_mut()[level] = position - pred_positions[level];
+
+                (*new_entry).nexts_mut()[level] = succs[level];
+                pred.nexts_mut()[level] = new_entry;
+            }
+
+            for (level, &pred) in preds[..self.max_level]
+                .iter()
+                .enumerate()
+                .skip(top_level + 1)
+            {
+                (*pred).widths_mut()[level] += 1;
             }
         }
 
-        return true;
+        self.len += 1;
+        self.current_height = self.current_height.max(top_level + 1);
+
+        new_entry
     }
 
-    pub fn remove(&mut self, key: K) -> Option<V> {
-        let mut preds = vec![null_mut(); MAX_LEVEL + 1];
-        let mut succs = vec![null_mut(); MAX_LEVEL + 1];
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        let mut preds = vec![null_mut(); self.max_level];
+        let mut succs = vec![null_mut(); self.max_level];
+
+        let level_found = self.find(key, &mut preds, &mut succs)?;
 
-        let key = Key::Entry(key);
+        Some(unsafe { self.unlink(succs[level_found], level_found, &preds) })
+    }
 
-        let level_found = self.find(&key, &mut preds, &mut succs);
+    /// Unlinks `entry`, whose top level is `level_found`, from the `preds`
+    /// found by a search for it.
+    unsafe fn unlink(
+        &mut self,
+        entry_to_remove: *mut Entry<K, V, C>,
+        level_found: usize,
+        preds: &[*mut Entry<K, V, C>],
+    ) -> (K, V) {
+        for level in (0..=level_found).rev() {
+            (*preds[level]).nexts_mut()[level] = (*entry_to_remove).nexts()[level];
+            (*preds[level]).widths_mut()[level] += (*entry_to_remove).widths()[level] - 1;
+        }
+
+        for (level, &pred) in preds[..self.max_level]
+            .iter()
+            .enumerate()
+            .skip(level_found + 1)
+        {
+            (*pred).widths_mut()[level] -= 1;
+        }
 
-        if level_found.is_none() {
+        self.len -= 1;
+
+        Entry::take(entry_to_remove).into_key_value()
+    }
+
+    /// Unlinks the smallest entry. It is linked straight from the head on
+    /// every level it has, so no search is needed.
+    fn pop_first_entry(&mut self) -> Option<(K, V)> {
+        unsafe {
+            let first = (*self.head).nexts()[0];
+
+            if let Key::Tail = (*first).key {
+                return None;
+            }
+
+            let height = (*first).nexts().len();
+
+            for level in 0..height {
+                (*self.head).nexts_mut()[level] = (*first).nexts()[level];
+                (*self.head).widths_mut()[level] = (*first).widths()[level];
+            }
+
+            for level in height..self.max_level {
+                (*self.head).widths_mut()[level] -= 1;
+            }
+
+            self.len -= 1;
+
+            Some(Entry::take(first).into_key_value())
+        }
+    }
+
+    fn pop_last_entry(&mut self) -> Option<(K, V)> {
+        unsafe {
+            let last = self.predecessor(self.tail);
+
+            let Key::Entry(key) = &(*last).key else {
+                return None;
+            };
+
+            self.remove_entry(key)
+        }
+    }
+
+    /// Returns the entry right before `entry` on the bottom level.
+    ///
+    /// Only touches keys and links, read through raw pointers without
+    /// borrowing whole entries, so it is safe to call while values handed
+    /// out by [`IterMut`] are borrowed.
+    unsafe fn predecessor(&self, entry: *mut Entry<K, V, C>) -> *mut Entry<K, V, C> {
+        let key = match &(*entry).key {
+            Key::Entry(key) => Some(key),
+            _ => None,
+        };
+
+        let mut pred = self.head;
+
+        for level in (0..self.current_height).rev() {
+            loop {
+                let next = *Entry::nexts_ptr(pred).add(level);
+
+                let before = match (&(*next).key, key) {
+                    (Key::Tail, _) => false,
+                    (_, None) => true,
+                    (next_key, Some(key)) => next_key.cmp_by(key, &self.key_cmp).is_lt(),
+                };
+
+                if !before {
+                    break;
+                }
+
+                pred = next;
+            }
+        }
+
+        pred
+    }
+
+    /// Returns the entry with the largest key less than or equal to `key`.
+    pub fn floor<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        let mut preds = vec![null_mut(); self.max_level];
+        let mut succs = vec![null_mut(); self.max_level];
+
+        let floor = match self.find(key, &mut preds, &mut succs) {
+            Some(_) => succs[0],
+            None => preds[0],
+        };
+
+        unsafe { (*floor).key_value() }
+    }
+
+    /// Returns the entry with the smallest key greater than or equal to
+    /// `key`.
+    pub fn ceiling<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        unsafe { (*self.seek(Bound::Included(key))).key_value() }
+    }
+
+    /// Returns the entry with the largest key strictly less than `key`.
+    pub fn lower<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        let mut preds = vec![null_mut(); self.max_level];
+        let mut succs = vec![null_mut(); self.max_level];
+
+        self.find(key, &mut preds, &mut succs);
+
+        unsafe { (*preds[0]).key_value() }
+    }
+
+    /// Returns the entry with the smallest key strictly greater than `key`.
+    pub fn higher<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        unsafe { (*self.seek(Bound::Excluded(key))).key_value() }
+    }
+
+    /// Returns the entry with the first key not less than `key`, as C++'s
+    /// `lower_bound` does; the same entry as [`ceiling`](Self::ceiling).
+    pub fn lower_bound<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        self.ceiling(key)
+    }
+
+    /// Returns the entry with the first key greater than `key`, as C++'s
+    /// `upper_bound` does; the same entry as [`higher`](Self::higher).
+    pub fn upper_bound<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        self.higher(key)
+    }
+
+    /// Returns the entry with the smallest key.
+    pub fn first(&self) -> Option<(&K, &V)> {
+        unsafe { (*(*self.head).nexts()[0]).key_value() }
+    }
+
+    /// Returns the entry with the largest key.
+    pub fn last(&self) -> Option<(&K, &V)> {
+        unsafe { (*self.predecessor(self.tail)).key_value() }
+    }
+
+    /// Returns the bottom-level position of each of `preds`, the head being
+    /// at 0, by walking again the search path they were found on.
+    unsafe fn positions(&self, preds: &[*mut Entry<K, V, C>]) -> Vec<usize> {
+        let mut positions = vec![0; self.max_level];
+
+        let mut pred = self.head;
+        let mut position = 0;
+
+        for level in (0..self.current_height).rev() {
+            while pred != preds[level] {
+                position += (*pred).widths()[level];
+                pred = (*pred).nexts()[level];
+            }
+
+            positions[level] = position;
+        }
+
+        positions
+    }
+
+    /// Returns the entry at bottom-level `position`, which must hold a real
+    /// entry, and fills `preds` with the last entry before it on each level.
+    unsafe fn seek_position(
+        &self,
+        position: usize,
+        preds: &mut [*mut Entry<K, V, C>],
+    ) -> *mut Entry<K, V, C> {
+        let mut pred = self.head;
+        let mut pred_position = 0;
+
+        preds[self.current_height..self.max_level].fill(pred);
+
+        for level in (0..self.current_height).rev() {
+            while pred_position + (*pred).widths()[level] < position {
+                pred_position += (*pred).widths()[level];
+                pred = (*pred).nexts()[level];
+            }
+
+            preds[level] = pred;
+        }
+
+        (*pred).nexts()[0]
+    }
+
+    /// Counts the entries whose keys are less than `key`, or no greater than
+    /// it if `inclusive`, by summing the widths of the links a search skips.
+    fn count_before<Q>(&self, key: &Q, inclusive: bool) -> usize
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        let mut pred = unsafe { &*self.head };
+        let mut position = 0;
+
+        for level in (0..self.current_height).rev() {
+            loop {
+                let next = unsafe { &*pred.nexts()[level] };
+                let order = next.key.cmp_by(key, &self.key_cmp);
+
+                if !(order.is_lt() || (inclusive && order.is_eq())) {
+                    break;
+                }
+
+                position += pred.widths()[level];
+                pred = next;
+            }
+        }
+
+        position
+    }
+
+    /// Returns the entry at `index` in key order, counting from 0, in
+    /// `O(log n)`.
+    pub fn get_by_index(&self, index: usize) -> Option<(&K, &V)> {
+        if index >= self.len {
             return None;
         }
 
-        let entry_to_remove = succs[level_found.unwrap()];
+        let mut preds = vec![null_mut(); self.max_level];
+
+        unsafe {
+            let entry = &*self.seek_position(index + 1, &mut preds);
+
+            let Key::Entry(key) = &entry.key else {
+                unreachable!("positions within the length hold real entries")
+            };
+
+            Some((key, entry.value.assume_init_ref()))
+        }
+    }
+
+    /// Removes the entry at `index` in key order, counting from 0, and
+    /// returns its key and value.
+    pub fn remove_by_index(&mut self, index: usize) -> Option<(K, V)> {
+        if index >= self.len {
+            return None;
+        }
+
+        let mut preds = vec![null_mut(); self.max_level];
+
+        unsafe {
+            let entry = self.seek_position(index + 1, &mut preds);
+            let level_found = (*entry).nexts().len() - 1;
+
+            Some(self.unlink(entry, level_found, &preds))
+        }
+    }
+
+    /// Returns how many keys are less than `key`, which is the index `key`
+    /// has or would have in key order.
+    pub fn rank<Q>(&self, key: &Q) -> usize
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        self.count_before(key, false)
+    }
+
+    /// Returns how many keys lie in `range`, in `O(log n)`.
+    pub fn len_in_range<Q, R>(&self, range: R) -> usize
+    where
+        Q: ?Sized,
+        R: RangeBounds<Q>,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(start) => self.count_before(start, false),
+            Bound::Excluded(start) => self.count_before(start, true),
+            Bound::Unbounded => 0,
+        };
+
+        let end = match range.end_bound() {
+            Bound::Included(end) => self.count_before(end, true),
+            Bound::Excluded(end) => self.count_before(end, false),
+            Bound::Unbounded => self.len,
+        };
+
+        end.saturating_sub(start)
+    }
+
+    /// Returns the number of entries. It is always exact.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V, C> {
+        Iter::new(self)
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V, C> {
+        IterMut::new(self)
+    }
+
+    pub fn keys(&self) -> Keys<'_, K, V, C> {
+        Keys(self.iter())
+    }
+
+    pub fn values(&self) -> Values<'_, K, V, C> {
+        Values(self.iter())
+    }
+
+    /// Returns an iterator over the entries whose keys lie in `range`, in
+    /// order. Both ends, and the number of entries between them, are located
+    /// by searches from the head, so only the entries in the range are
+    /// visited. A range whose start lies past its end is empty.
+    pub fn range<Q, R>(&self, range: R) -> Range<'_, K, V, C>
+    where
+        Q: ?Sized,
+        R: RangeBounds<Q>,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        let len = self.len_in_range::<Q, _>((range.start_bound(), range.end_bound()));
+        let mut front = self.seek(range.start_bound());
+
+        let back = match range.end_bound() {
+            Bound::Included(end) => self.seek(Bound::Excluded(end)),
+            Bound::Excluded(end) => self.seek(Bound::Included(end)),
+            Bound::Unbounded => self.tail,
+        };
 
         unsafe {
-            for level in (0..=level_found.unwrap()).rev() {
-                (*preds[level]).nexts[level] = (*entry_to_remove).nexts[level];
+            if let Key::Entry(back_key) = &(*back).key {
+                if !(*front).key.cmp_by::<K, C>(back_key, &self.key_cmp).is_lt() {
+                    front = back;
+                }
             }
         }
 
-        // We uses box as a allocator
-        let entry_to_remove = unsafe { Box::from_raw(entry_to_remove) };
+        Range(Iter::between(self, front, back, len))
+    }
+
+    /// Returns the first entry that does not lie before `bound`, taken as the
+    /// start of a range, or the tail if there is none.
+    fn seek<Q>(&self, bound: Bound<&Q>) -> *mut Entry<K, V, C>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        let key = match bound {
+            Bound::Included(key) | Bound::Excluded(key) => key,
+            Bound::Unbounded => return unsafe { (*self.head).nexts()[0] },
+        };
+
+        let mut preds = vec![null_mut(); self.max_level];
+        let mut succs = vec![null_mut(); self.max_level];
 
-        return entry_to_remove.value;
+        self.find(key, &mut preds, &mut succs);
+
+        let mut start = succs[0];
+
+        // A multimap looks keys up on their own, which several entries may
+        // compare equal to; an excluded bound skips all of them.
+        if matches!(bound, Bound::Excluded(_)) {
+            unsafe {
+                while (*start).key.cmp_by(key, &self.key_cmp).is_eq() {
+                    start = (*start).nexts()[0];
+                }
+            }
+        }
+
+        start
     }
 
-    fn find<'a, 'b>(
+    fn find<'a, 'b, Q>(
         &'a self,
-        key: &Key<K>,
-        preds: &'b mut Vec<*mut Entry<K, V, C>>,
-        succs: &'b mut Vec<*mut Entry<K, V, C>>,
+        key: &Q,
+        preds: &'b mut [*mut Entry<K, V, C>],
+        succs: &'b mut [*mut Entry<K, V, C>],
     ) -> Option<usize>
     where
         'a: 'b,
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
     {
         let head = self.head;
 
-        let mut level_found = None;
-
         let mut pred = unsafe { &mut *head };
 
-        for level in (0..MAX_LEVEL).rev() {
-            let mut current = unsafe { pred.nexts[level].as_mut().unwrap() };
+        for level in self.current_height..self.max_level {
+            preds[level] = head;
+            succs[level] = self.tail;
+        }
+
+        for level in (0..self.current_height).rev() {
+            let mut current = unsafe { pred.nexts()[level].as_mut().unwrap() };
 
-            while current.key < *key {
+            while current.key.cmp_by(key, &self.key_cmp).is_lt() {
                 pred = current;
-                current = unsafe { pred.nexts[level].as_mut().unwrap() };
-            }
-
-            if level_found.is_none() && current.key == *key {
-                level_found = Some(level);
+                current = unsafe { pred.nexts()[level].as_mut().unwrap() };
             }
 
             preds[level] = pred;
             succs[level] = current;
         }
 
-        return level_found;
-    }
+        // Only the first entry equal to `key` counts as found, as it is the
+        // one linked from `preds` on every level it has, even when a multimap
+        // holds several entries that compare equal to `key`.
+        let found = unsafe { &*succs[0] };
 
-    pub fn get(&self, key: K) -> Option<&V> {
-        let mut pred = self.head;
+        if found.key.cmp_by(key, &self.key_cmp).is_eq() {
+            Some(found.nexts().len() - 1)
+        } else {
+            None
+        }
+    }
 
-        let key = Key::Entry(key);
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        let mut pred = unsafe { &*self.head };
 
-        for level in (0..MAX_LEVEL).rev() {
-            let mut current = unsafe { (*pred).nexts[level].as_mut().unwrap() };
+        for level in (0..self.current_height).rev() {
+            let mut current = unsafe { &*pred.nexts()[level] };
 
-            while current.key < key {
+            while current.key.cmp_by(key, &self.key_cmp).is_lt() {
                 pred = current;
-                current = unsafe { (*current).nexts[level].as_mut().unwrap() };
+                current = unsafe { &*current.nexts()[level] };
             }
 
-            if current.key == key {
-                return current.value.as_ref();
+            if current.key.cmp_by(key, &self.key_cmp).is_eq() {
+                return Some(unsafe { current.value.assume_init_ref() });
             }
         }
 
-        return None;
+        None
     }
 }
 
-static P: f32 = 0.5;
-static MAX_LEVEL: usize = 32;
+impl<K, V, C> Drop for SkipList<K, V, C>
+where
+    C: Comparator<K>,
+{
+    fn drop(&mut self) {
+        // Every entry, head and tail included, is linked at the bottom level.
+        let mut current = self.head;
+
+        while !current.is_null() {
+            let next = unsafe { (*current).nexts()[0] };
+            drop(unsafe { Entry::take(current) });
+            current = next;
+        }
+    }
+}
 
-fn random_level() -> usize {
-    let level = (f32::log2(1. - random::<f32>()) / f32::log2(1. - P)) as usize;
-    return min(level, MAX_LEVEL);
+impl<K, V, C> OrderedMap<K, V> for SkipList<K, V, C>
+where
+    C: Comparator<K>,
+{
+    type Ref<'a>
+        = &'a V
+    where
+        Self: 'a;
+
+    fn add(&mut self, key: K, value: V) -> bool {
+        SkipList::add(self, key, value)
+    }
+
+    fn try_insert(&mut self, key: K, value: V) -> Result<(), (K, V)> {
+        SkipList::try_insert(self, key, value)
+    }
+
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        SkipList::insert(self, key, value)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        SkipList::remove(self, key)
+    }
+
+    fn get(&self, key: &K) -> Option<&V> {
+        SkipList::get(self, key)
+    }
+
+    fn len(&self) -> usize {
+        SkipList::len(self)
+    }
 }
@@ -0,0 +1,189 @@
+use super::entry::{Entry, Key};
+use super::SkipList;
+use crate::Comparator;
+
+/// A cursor over a [`SkipList`] that can insert and remove entries where it
+/// stands. Created by [`SkipList::cursor_front_mut`] and
+/// [`SkipList::cursor_mut_at`].
+///
+/// The cursor rests on an entry, or at the end past the last one. Besides
+/// that entry it keeps, for every level, the last entry before it and that
+/// entry's position, which is exactly what linking or unlinking next to it
+/// takes; so moving, inserting and removing never search from the head.
+pub struct CursorMut<'a, K, V, C>
+where
+    C: Comparator<K>,
+{
+    list: &'a mut SkipList<K, V, C>,
+    // The entry the cursor rests on, or the tail at the end.
+    current: *mut Entry<K, V, C>,
+    // The last entry before `current` on each level.
+    preds: Vec<*mut Entry<K, V, C>>,
+    // The bottom-level position of each of `preds`, the head being at 0.
+    positions: Vec<usize>,
+}
+
+impl<'a, K, V, C> CursorMut<'a, K, V, C>
+where
+    C: Comparator<K>,
+{
+    pub(super) fn new(
+        list: &'a mut SkipList<K, V, C>,
+        current: *mut Entry<K, V, C>,
+        preds: Vec<*mut Entry<K, V, C>>,
+        positions: Vec<usize>,
+    ) -> Self {
+        CursorMut {
+            list,
+            current,
+            preds,
+            positions,
+        }
+    }
+
+    /// Returns the entry the cursor rests on, or `None` at the end.
+    pub fn current(&mut self) -> Option<(&K, &mut V)> {
+        let entry = unsafe { &mut *self.current };
+
+        match &entry.key {
+            Key::Entry(key) => Some((key, unsafe { entry.value.assume_init_mut() })),
+            _ => None,
+        }
+    }
+
+    /// Returns the index of the entry the cursor rests on, or the length of
+    /// the list at the end.
+    pub fn index(&self) -> usize {
+        self.positions[0]
+    }
+
+    /// Returns the entry after the one the cursor rests on.
+    pub fn peek_next(&self) -> Option<(&K, &V)> {
+        unsafe {
+            if let Key::Tail = (*self.current).key {
+                return None;
+            }
+
+            (*(*self.current).nexts()[0]).key_value()
+        }
+    }
+
+    /// Moves to the next entry, or to the end after the last one. Does
+    /// nothing at the end.
+    pub fn move_next(&mut self) {
+        let current = unsafe { &*self.current };
+
+        if let Key::Tail = current.key {
+            return;
+        }
+
+        // The entry left behind is the last one before the next entry on
+        // every level it has.
+        let position = self.positions[0] + 1;
+
+        for level in 0..current.nexts().len() {
+            self.preds[level] = self.current;
+            self.positions[level] = position;
+        }
+
+        self.current = current.nexts()[0];
+    }
+
+    /// Removes the entry the cursor rests on and returns it, moving to the
+    /// next entry. Returns `None` at the end.
+    pub fn remove_current(&mut self) -> Option<(K, V)> {
+        unsafe {
+            if let Key::Tail = (*self.current).key {
+                return None;
+            }
+
+            // The preds of the removed entry are those of the next one too.
+            let next = (*self.current).nexts()[0];
+            let top_level = (*self.current).nexts().len() - 1;
+            let entry = self.list.unlink(self.current, top_level, &self.preds);
+
+            self.current = next;
+
+            Some(entry)
+        }
+    }
+
+    /// Inserts `key` with `value` right after the entry the cursor rests on,
+    /// without moving. Hands both back if `key` does not sort strictly
+    /// between that entry and the next one, or if the cursor is at the end.
+    pub fn insert_after(&mut self, key: K, value: V) -> Result<(), (K, V)> {
+        unsafe {
+            let current = &*self.current;
+
+            if let Key::Tail = current.key {
+                return Err((key, value));
+            }
+
+            let next = &*current.nexts()[0];
+
+            if !current.key.cmp_by(&key, &self.list.key_cmp).is_lt()
+                || !next.key.cmp_by(&key, &self.list.key_cmp).is_gt()
+            {
+                return Err((key, value));
+            }
+
+            // Up to its top level the current entry precedes the new one;
+            // above it, the cursor's own preds do.
+            let mut preds = self.preds.clone();
+            let mut positions = self.positions.clone();
+
+            for level in 0..current.nexts().len() {
+                preds[level] = self.current;
+                positions[level] = self.positions[0] + 1;
+            }
+
+            let succs = self.succs_of(&preds);
+
+            self.list.link_at(key, value, &preds, &succs, &positions);
+        }
+
+        Ok(())
+    }
+
+    /// Inserts `key` with `value` right before the entry the cursor rests
+    /// on, or at the back at the end, without moving. Hands both back if
+    /// `key` does not sort strictly between the previous entry and that one.
+    pub fn insert_before(&mut self, key: K, value: V) -> Result<(), (K, V)> {
+        unsafe {
+            let pred = &*self.preds[0];
+
+            if !pred.key.cmp_by(&key, &self.list.key_cmp).is_lt()
+                || !(*self.current).key.cmp_by(&key, &self.list.key_cmp).is_gt()
+            {
+                return Err((key, value));
+            }
+
+            let succs = self.succs_of(&self.preds);
+            let new_entry = self
+                .list
+                .link_at(key, value, &self.preds, &succs, &self.positions);
+
+            // The new entry is now the last one before the cursor on every
+            // level it has.
+            let position = self.positions[0] + 1;
+
+            for level in 0..(*new_entry).nexts().len() {
+                self.preds[level] = new_entry;
+                self.positions[level] = position;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The entries `preds` link to on each level.
+    unsafe fn succs_of(&self, preds: &[*mut Entry<K, V, C>]) -> Vec<*mut Entry<K, V, C>> {
+        let mut succs = preds.to_vec();
+
+        for level in 0..self.list.max_level {
+            succs[level] = (*preds[level]).nexts()[level];
+        }
+
+        succs
+    }
+}
@@ -1,36 +1,166 @@
-use std::{cmp::Ordering};
+use std::alloc::{alloc, dealloc, handle_alloc_error, Layout};
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::mem::{offset_of, ManuallyDrop, MaybeUninit};
+use std::ptr::{self, null_mut};
+use std::slice;
 
+use crate::Comparator;
+
+/// An entry is allocated in one piece: this header, then its tower of
+/// `height` links, then as many widths.
+#[repr(C)]
 pub(crate) struct Entry<K, V, C>
 where
-    K: Ord,
-    C: Fn(&K, &K) -> Ordering,
+    C: Comparator<K>,
 {
     pub key: Key<K>,
-    pub value: Option<V>,
-    pub nexts: Vec<*mut Entry<K, V, C>>,
+    /// Initialized exactly when `key` is a real one. The head and tail hold
+    /// none, and a `()` takes no room.
+    pub value: MaybeUninit<V>,
+    height: usize,
+    tower: [*mut Entry<K, V, C>; 0],
 }
 
-#[derive(PartialEq)]
-pub(crate) enum Key<K>
+impl<K, V, C> Entry<K, V, C>
 where
-    K: Ord,
+    C: Comparator<K>,
 {
+    /// Allocates an entry with `height` levels, its links null and its
+    /// widths all `width`.
+    pub fn alloc(key: Key<K>, value: Option<V>, height: usize, width: usize) -> *mut Self {
+        let layout = Self::layout(height);
+
+        unsafe {
+            let entry = alloc(layout) as *mut Self;
+
+            if entry.is_null() {
+                handle_alloc_error(layout);
+            }
+
+            entry.write(Entry {
+                key,
+                value: value.map_or_else(MaybeUninit::uninit, MaybeUninit::new),
+                height,
+                tower: [],
+            });
+
+            let nexts = Self::nexts_ptr(entry);
+            let widths = nexts.add(height) as *mut usize;
+
+            for level in 0..height {
+                nexts.add(level).write(null_mut());
+                widths.add(level).write(width);
+            }
+
+            entry
+        }
+    }
+
+    /// Moves the header out of an entry made by [`Entry::alloc`] and frees
+    /// the allocation, tower included.
+    pub unsafe fn take(entry: *mut Self) -> Self {
+        let layout = Self::layout((*entry).height);
+        let header = entry.read();
+
+        dealloc(entry as *mut u8, layout);
+
+        header
+    }
+
+    fn layout(height: usize) -> Layout {
+        // Links and widths have the same size and alignment, so the widths
+        // follow the links without padding.
+        let header = Layout::from_size_align(offset_of!(Self, tower), align_of::<Self>()).unwrap();
+        let tower = Layout::array::<*mut Self>(height).unwrap();
+        let widths = Layout::array::<usize>(height).unwrap();
+
+        let (layout, _) = header.extend(tower).unwrap();
+        let (layout, _) = layout.extend(widths).unwrap();
+
+        layout.pad_to_align()
+    }
+
+    /// Points at the first link of `entry` without borrowing the entry, so
+    /// links can be read while its value is borrowed elsewhere.
+    pub unsafe fn nexts_ptr(entry: *const Self) -> *mut *mut Self {
+        ptr::addr_of!((*entry).tower) as *mut *mut Self
+    }
+
+    pub fn nexts(&self) -> &[*mut Self] {
+        unsafe { slice::from_raw_parts(self.tower.as_ptr(), self.height) }
+    }
+
+    pub fn nexts_mut(&mut self) -> &mut [*mut Self] {
+        unsafe { slice::from_raw_parts_mut(self.tower.as_mut_ptr(), self.height) }
+    }
+
+    /// How many bottom-level steps each link in `nexts` spans, which is how
+    /// far the entry it points to lies past this one.
+    pub fn widths(&self) -> &[usize] {
+        unsafe {
+            let widths = self.tower.as_ptr().add(self.height) as *const usize;
+            slice::from_raw_parts(widths, self.height)
+        }
+    }
+
+    pub fn widths_mut(&mut self) -> &mut [usize] {
+        unsafe {
+            let widths = self.tower.as_mut_ptr().add(self.height) as *mut usize;
+            slice::from_raw_parts_mut(widths, self.height)
+        }
+    }
+
+    /// Borrows the key and value, unless this is the head or the tail.
+    pub fn key_value(&self) -> Option<(&K, &V)> {
+        match &self.key {
+            Key::Entry(key) => Some((key, unsafe { self.value.assume_init_ref() })),
+            _ => None,
+        }
+    }
+
+    /// Takes apart an entry that holds a real key.
+    pub fn into_key_value(self) -> (K, V) {
+        let entry = ManuallyDrop::new(self);
+
+        let Key::Entry(key) = (unsafe { ptr::read(&entry.key) }) else {
+            unreachable!("head and tail hold no value")
+        };
+
+        (key, unsafe { entry.value.assume_init_read() })
+    }
+}
+
+impl<K, V, C> Drop for Entry<K, V, C>
+where
+    C: Comparator<K>,
+{
+    fn drop(&mut self) {
+        if let Key::Entry(_) = self.key {
+            unsafe { self.value.assume_init_drop() };
+        }
+    }
+}
+
+pub(crate) enum Key<K> {
     Head,
     Entry(K),
     Tail,
 }
 
-
-impl<K> PartialOrd for Key<K> where K: Ord {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        match (self, other) {
-            (Key::Head, Key::Head) => Some(Ordering::Equal),
-            (Key::Head, _) => Some(Ordering::Less),
-            (_, Key::Head) => Some(Ordering::Greater),
-            (Key::Tail, Key::Tail) => Some(Ordering::Equal),
-            (Key::Tail, _) => Some(Ordering::Greater),
-            (_, Key::Tail) => Some(Ordering::Less),
-            (Key::Entry(k1), Key::Entry(k2)) => k1.partial_cmp(k2),
+impl<K> Key<K> {
+    /// Orders `self` against the real key `other` with `cmp`. The head sorts
+    /// before and the tail after every real key.
+    pub fn cmp_by<Q, C>(&self, other: &Q, cmp: &C) -> Ordering
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        match self {
+            Key::Head => Ordering::Less,
+            Key::Entry(key) => cmp.compare(key.borrow(), other),
+            Key::Tail => Ordering::Greater,
         }
     }
 }
@@ -0,0 +1,372 @@
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+
+use super::entry::{Entry, Key};
+use super::SkipList;
+use crate::Comparator;
+
+/// Borrows the key and value of a real entry.
+///
+/// Only the key and value fields are borrowed, never the whole entry, so the
+/// links stay readable while a value is borrowed mutably.
+unsafe fn key_value<'a, K, V, C>(entry: *mut Entry<K, V, C>) -> (&'a K, &'a V)
+where
+    C: Comparator<K>,
+{
+    let Key::Entry(key) = &(*entry).key else {
+        unreachable!("head and tail are never yielded")
+    };
+
+    (key, (*entry).value.assume_init_ref())
+}
+
+unsafe fn key_value_mut<'a, K, V, C>(entry: *mut Entry<K, V, C>) -> (&'a K, &'a mut V)
+where
+    C: Comparator<K>,
+{
+    let Key::Entry(key) = &(*entry).key else {
+        unreachable!("head and tail are never yielded")
+    };
+
+    (key, (*entry).value.assume_init_mut())
+}
+
+/// An iterator over the entries of a [`SkipList`], in key order.
+///
+/// Walking forwards follows the bottom level; each step backwards searches
+/// for the predecessor from the head, in `O(log n)`.
+pub struct Iter<'a, K, V, C>
+where
+    C: Comparator<K>,
+{
+    list: &'a SkipList<K, V, C>,
+    // Next entry to yield from the front.
+    front: *mut Entry<K, V, C>,
+    // Last entry yielded from the back, the tail at first.
+    back: *mut Entry<K, V, C>,
+    // Entries left between `front` and `back`.
+    remaining: usize,
+}
+
+impl<'a, K, V, C> Iter<'a, K, V, C>
+where
+    C: Comparator<K>,
+{
+    pub(super) fn new(list: &'a SkipList<K, V, C>) -> Self {
+        Iter {
+            front: unsafe { (*list.head).nexts()[0] },
+            back: list.tail,
+            remaining: list.len(),
+            list,
+        }
+    }
+
+    /// Yields the `len` entries from `front` up to, but not including,
+    /// `back`.
+    pub(super) fn between(
+        list: &'a SkipList<K, V, C>,
+        front: *mut Entry<K, V, C>,
+        back: *mut Entry<K, V, C>,
+        len: usize,
+    ) -> Self {
+        Iter {
+            list,
+            front,
+            back,
+            remaining: len,
+        }
+    }
+}
+
+impl<'a, K, V, C> Iterator for Iter<'a, K, V, C>
+where
+    C: Comparator<K>,
+{
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+
+        let entry = self.front;
+        self.remaining -= 1;
+
+        unsafe {
+            self.front = (*entry).nexts()[0];
+            Some(key_value(entry))
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V, C> DoubleEndedIterator for Iter<'_, K, V, C>
+where
+    C: Comparator<K>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+
+        self.remaining -= 1;
+
+        unsafe {
+            self.back = self.list.predecessor(self.back);
+            Some(key_value(self.back))
+        }
+    }
+}
+
+impl<K, V, C> ExactSizeIterator for Iter<'_, K, V, C> where C: Comparator<K> {}
+
+impl<K, V, C> FusedIterator for Iter<'_, K, V, C> where C: Comparator<K> {}
+
+/// A mutable iterator over the entries of a [`SkipList`], in key order.
+pub struct IterMut<'a, K, V, C>
+where
+    C: Comparator<K>,
+{
+    list: &'a SkipList<K, V, C>,
+    front: *mut Entry<K, V, C>,
+    back: *mut Entry<K, V, C>,
+    remaining: usize,
+    _marker: PhantomData<&'a mut V>,
+}
+
+impl<'a, K, V, C> IterMut<'a, K, V, C>
+where
+    C: Comparator<K>,
+{
+    pub(super) fn new(list: &'a mut SkipList<K, V, C>) -> Self {
+        IterMut {
+            front: unsafe { (*list.head).nexts()[0] },
+            back: list.tail,
+            remaining: list.len(),
+            list,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, K, V, C> Iterator for IterMut<'a, K, V, C>
+where
+    C: Comparator<K>,
+{
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+
+        let entry = self.front;
+        self.remaining -= 1;
+
+        unsafe {
+            self.front = *Entry::nexts_ptr(entry);
+            Some(key_value_mut(entry))
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V, C> DoubleEndedIterator for IterMut<'_, K, V, C>
+where
+    C: Comparator<K>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+
+        self.remaining -= 1;
+
+        unsafe {
+            self.back = self.list.predecessor(self.back);
+            Some(key_value_mut(self.back))
+        }
+    }
+}
+
+impl<K, V, C> ExactSizeIterator for IterMut<'_, K, V, C> where C: Comparator<K> {}
+
+impl<K, V, C> FusedIterator for IterMut<'_, K, V, C> where C: Comparator<K> {}
+
+/// An owning iterator over the entries of a [`SkipList`], in key order.
+pub struct IntoIter<K, V, C>
+where
+    C: Comparator<K>,
+{
+    list: SkipList<K, V, C>,
+}
+
+impl<K, V, C> Iterator for IntoIter<K, V, C>
+where
+    C: Comparator<K>,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.list.pop_first_entry()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len(), Some(self.list.len()))
+    }
+}
+
+impl<K, V, C> DoubleEndedIterator for IntoIter<K, V, C>
+where
+    C: Comparator<K>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.list.pop_last_entry()
+    }
+}
+
+impl<K, V, C> ExactSizeIterator for IntoIter<K, V, C> where C: Comparator<K> {}
+
+impl<K, V, C> FusedIterator for IntoIter<K, V, C> where C: Comparator<K> {}
+
+/// An iterator over the keys of a [`SkipList`], in order.
+pub struct Keys<'a, K, V, C>(pub(super) Iter<'a, K, V, C>)
+where
+    C: Comparator<K>;
+
+impl<'a, K, V, C> Iterator for Keys<'a, K, V, C>
+where
+    C: Comparator<K>,
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        self.0.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, V, C> DoubleEndedIterator for Keys<'_, K, V, C>
+where
+    C: Comparator<K>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(key, _)| key)
+    }
+}
+
+impl<K, V, C> ExactSizeIterator for Keys<'_, K, V, C> where C: Comparator<K> {}
+
+impl<K, V, C> FusedIterator for Keys<'_, K, V, C> where C: Comparator<K> {}
+
+/// An iterator over the values of a [`SkipList`], in key order.
+pub struct Values<'a, K, V, C>(pub(super) Iter<'a, K, V, C>)
+where
+    C: Comparator<K>;
+
+impl<'a, K, V, C> Iterator for Values<'a, K, V, C>
+where
+    C: Comparator<K>,
+{
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<&'a V> {
+        self.0.next().map(|(_, value)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, V, C> DoubleEndedIterator for Values<'_, K, V, C>
+where
+    C: Comparator<K>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(_, value)| value)
+    }
+}
+
+impl<K, V, C> ExactSizeIterator for Values<'_, K, V, C> where C: Comparator<K> {}
+
+impl<K, V, C> FusedIterator for Values<'_, K, V, C> where C: Comparator<K> {}
+
+/// An iterator over the entries of a [`SkipList`] whose keys lie in a range,
+/// in key order. Created by [`SkipList::range`].
+pub struct Range<'a, K, V, C>(pub(super) Iter<'a, K, V, C>)
+where
+    C: Comparator<K>;
+
+impl<'a, K, V, C> Iterator for Range<'a, K, V, C>
+where
+    C: Comparator<K>,
+{
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, V, C> DoubleEndedIterator for Range<'_, K, V, C>
+where
+    C: Comparator<K>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
+    }
+}
+
+impl<K, V, C> ExactSizeIterator for Range<'_, K, V, C> where C: Comparator<K> {}
+
+impl<K, V, C> FusedIterator for Range<'_, K, V, C> where C: Comparator<K> {}
+
+impl<K, V, C> IntoIterator for SkipList<K, V, C>
+where
+    C: Comparator<K>,
+{
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V, C>;
+
+    fn into_iter(self) -> IntoIter<K, V, C> {
+        IntoIter { list: self }
+    }
+}
+
+impl<'a, K, V, C> IntoIterator for &'a SkipList<K, V, C>
+where
+    C: Comparator<K>,
+{
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V, C>;
+
+    fn into_iter(self) -> Iter<'a, K, V, C> {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, C> IntoIterator for &'a mut SkipList<K, V, C>
+where
+    C: Comparator<K>,
+{
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V, C>;
+
+    fn into_iter(self) -> IterMut<'a, K, V, C> {
+        self.iter_mut()
+    }
+}
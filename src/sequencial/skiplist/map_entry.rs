@@ -0,0 +1,171 @@
+use super::entry::{self, Key};
+use super::SkipList;
+use crate::Comparator;
+
+/// A view into a single entry of a [`SkipList`], which is either vacant or
+/// occupied. Created by [`SkipList::entry`].
+///
+/// Both variants keep what the search for the key found, so filling or
+/// emptying the entry needs no second search.
+pub enum Entry<'a, K, V, C>
+where
+    C: Comparator<K>,
+{
+    Vacant(VacantEntry<'a, K, V, C>),
+    Occupied(OccupiedEntry<'a, K, V, C>),
+}
+
+/// A view into a vacant entry of a [`SkipList`].
+pub struct VacantEntry<'a, K, V, C>
+where
+    C: Comparator<K>,
+{
+    pub(super) list: &'a mut SkipList<K, V, C>,
+    pub(super) key: K,
+    pub(super) preds: Vec<*mut entry::Entry<K, V, C>>,
+    pub(super) succs: Vec<*mut entry::Entry<K, V, C>>,
+}
+
+/// A view into an occupied entry of a [`SkipList`].
+pub struct OccupiedEntry<'a, K, V, C>
+where
+    C: Comparator<K>,
+{
+    pub(super) list: &'a mut SkipList<K, V, C>,
+    pub(super) entry: *mut entry::Entry<K, V, C>,
+    // Top level of `entry`, which it is linked from `preds` up to.
+    pub(super) level_found: usize,
+    pub(super) preds: Vec<*mut entry::Entry<K, V, C>>,
+}
+
+impl<'a, K, V, C> Entry<'a, K, V, C>
+where
+    C: Comparator<K>,
+{
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Vacant(entry) => entry.key(),
+            Entry::Occupied(entry) => entry.key(),
+        }
+    }
+
+    /// Inserts `default` if the entry is vacant, and returns the value.
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    /// Inserts the result of `default` if the entry is vacant, and returns
+    /// the value. `default` is only called when the entry is vacant.
+    pub fn or_insert_with<F>(self, default: F) -> &'a mut V
+    where
+        F: FnOnce() -> V,
+    {
+        match self {
+            Entry::Vacant(entry) => entry.insert(default()),
+            Entry::Occupied(entry) => entry.into_mut(),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    /// Calls `f` on the value if the entry is occupied.
+    pub fn and_modify<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(&mut V),
+    {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+
+        self
+    }
+
+    /// Sets the value of the entry, whether it was vacant or not, and
+    /// returns the occupied entry.
+    pub fn insert(self, value: V) -> OccupiedEntry<'a, K, V, C> {
+        match self {
+            Entry::Vacant(entry) => entry.insert_entry(value),
+            Entry::Occupied(mut entry) => {
+                entry.insert(value);
+                entry
+            }
+        }
+    }
+}
+
+impl<'a, K, V, C> VacantEntry<'a, K, V, C>
+where
+    C: Comparator<K>,
+{
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    /// Inserts `value` under the entry's key and returns it.
+    pub fn insert(self, value: V) -> &'a mut V {
+        self.insert_entry(value).into_mut()
+    }
+
+    fn insert_entry(self, value: V) -> OccupiedEntry<'a, K, V, C> {
+        let entry = self.list.link(self.key, value, &self.preds, &self.succs);
+        let level_found = unsafe { (*entry).nexts().len() - 1 };
+
+        OccupiedEntry {
+            list: self.list,
+            entry,
+            level_found,
+            preds: self.preds,
+        }
+    }
+}
+
+impl<'a, K, V, C> OccupiedEntry<'a, K, V, C>
+where
+    C: Comparator<K>,
+{
+    pub fn key(&self) -> &K {
+        let Key::Entry(key) = (unsafe { &(*self.entry).key }) else {
+            unreachable!("head and tail are never found")
+        };
+
+        key
+    }
+
+    pub fn get(&self) -> &V {
+        unsafe { (*self.entry).value.assume_init_ref() }
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        unsafe { (*self.entry).value.assume_init_mut() }
+    }
+
+    /// Turns the entry into a reference to its value that lives as long as
+    /// the borrow of the list.
+    pub fn into_mut(self) -> &'a mut V {
+        unsafe { (*self.entry).value.assume_init_mut() }
+    }
+
+    /// Replaces the value and returns the old one.
+    pub fn insert(&mut self, value: V) -> V {
+        std::mem::replace(self.get_mut(), value)
+    }
+
+    /// Removes the entry and returns its value.
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    /// Removes the entry and returns its key and value.
+    pub fn remove_entry(self) -> (K, V) {
+        unsafe { self.list.unlink(self.entry, self.level_found, &self.preds) }
+    }
+}
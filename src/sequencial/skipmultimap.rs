@@ -0,0 +1,171 @@
+use std::borrow::Borrow;
+use std::iter::FusedIterator;
+use std::ops::Bound;
+
+use super::skiplist::{Iter, Range, SkipList};
+use crate::multi_key::{KeyOnly, MultiCmp, MultiKey};
+use crate::{Comparator, OrdComparator};
+
+/// An ordered multimap, which keeps every value inserted for a key.
+///
+/// Values for equal keys are kept in insertion order: each one is stored
+/// under its key and a sequence number, so the underlying [`SkipList`] still
+/// holds unique keys.
+pub struct SkipMultiMap<K, V, C = OrdComparator>
+where
+    C: Comparator<K>,
+{
+    list: SkipList<MultiKey<K>, V, MultiCmp<C>>,
+    next_seq: u64,
+}
+
+impl<K, V> SkipMultiMap<K, V>
+where
+    K: Ord,
+{
+    pub fn new() -> Self {
+        SkipMultiMap::with_cmp(OrdComparator)
+    }
+}
+
+impl<K, V> Default for SkipMultiMap<K, V>
+where
+    K: Ord,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, C> SkipMultiMap<K, V, C>
+where
+    C: Comparator<K>,
+{
+    pub fn with_cmp(cmp: C) -> Self {
+        SkipMultiMap {
+            list: SkipList::with_cmp(MultiCmp(cmp)),
+            next_seq: 0,
+        }
+    }
+
+    /// Adds `value` under `key`, after every value already held for it.
+    pub fn insert(&mut self, key: K, value: V) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+
+        self.list.add(MultiKey { key, seq }, value);
+    }
+
+    /// Returns an iterator over the values held for `key`, in insertion
+    /// order.
+    pub fn get_all<Q>(&self, key: &Q) -> GetAll<'_, K, V, C>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        let key = KeyOnly::new(key);
+
+        GetAll(
+            self.list
+                .range::<KeyOnly<Q>, _>((Bound::Included(key), Bound::Included(key))),
+        )
+    }
+
+    /// Removes the oldest value held for `key` and returns it.
+    pub fn remove_one<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        self.list.remove::<KeyOnly<Q>>(KeyOnly::new(key))
+    }
+
+    /// Removes every value held for `key`. Returns how many there were.
+    pub fn remove_all<Q>(&mut self, key: &Q) -> usize
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        let mut removed = 0;
+
+        while self.remove_one(key).is_some() {
+            removed += 1;
+        }
+
+        removed
+    }
+
+    /// Returns the number of values, counting each value of a key.
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    /// Returns an iterator over every key and value, in key order and then in
+    /// insertion order.
+    pub fn iter(&self) -> MultiIter<'_, K, V, C> {
+        MultiIter(self.list.iter())
+    }
+}
+
+/// An iterator over the values held for one key of a [`SkipMultiMap`], in
+/// insertion order. Created by [`SkipMultiMap::get_all`].
+pub struct GetAll<'a, K, V, C>(Range<'a, MultiKey<K>, V, MultiCmp<C>>)
+where
+    C: Comparator<K>;
+
+impl<'a, K, V, C> Iterator for GetAll<'a, K, V, C>
+where
+    C: Comparator<K>,
+{
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<&'a V> {
+        self.0.next().map(|(_, value)| value)
+    }
+}
+
+impl<K, V, C> DoubleEndedIterator for GetAll<'_, K, V, C>
+where
+    C: Comparator<K>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(_, value)| value)
+    }
+}
+
+impl<K, V, C> FusedIterator for GetAll<'_, K, V, C> where C: Comparator<K> {}
+
+/// An iterator over the entries of a [`SkipMultiMap`], in key order and then
+/// in insertion order.
+pub struct MultiIter<'a, K, V, C>(Iter<'a, MultiKey<K>, V, MultiCmp<C>>)
+where
+    C: Comparator<K>;
+
+impl<'a, K, V, C> Iterator for MultiIter<'a, K, V, C>
+where
+    C: Comparator<K>,
+{
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, value)| (&key.key, value))
+    }
+}
+
+impl<K, V, C> DoubleEndedIterator for MultiIter<'_, K, V, C>
+where
+    C: Comparator<K>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(key, value)| (&key.key, value))
+    }
+}
+
+impl<K, V, C> FusedIterator for MultiIter<'_, K, V, C> where C: Comparator<K> {}
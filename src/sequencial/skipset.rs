@@ -0,0 +1,328 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::iter::{FusedIterator, Peekable};
+use std::ops::RangeBounds;
+
+use super::skiplist::{Keys, Range, SkipList};
+use crate::{Comparator, OrdComparator};
+
+/// An ordered set, kept as a [`SkipList`] whose values are all `()`.
+pub struct SkipSet<K, C = OrdComparator>
+where
+    C: Comparator<K>,
+{
+    list: SkipList<K, (), C>,
+}
+
+impl<K> SkipSet<K>
+where
+    K: Ord,
+{
+    pub fn new() -> Self {
+        SkipSet::with_cmp(OrdComparator)
+    }
+}
+
+impl<K> Default for SkipSet<K>
+where
+    K: Ord,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, C> SkipSet<K, C>
+where
+    C: Comparator<K>,
+{
+    pub fn with_cmp(cmp: C) -> Self {
+        SkipSet {
+            list: SkipList::with_cmp(cmp),
+        }
+    }
+
+    /// Adds `key` to the set. Returns whether it was absent.
+    pub fn insert(&mut self, key: K) -> bool {
+        self.list.add(key, ())
+    }
+
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        self.list.get(key).is_some()
+    }
+
+    /// Removes `key` from the set. Returns whether it was present.
+    pub fn remove<Q>(&mut self, key: &Q) -> bool
+    where
+        Q: ?Sized,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        self.list.remove(key).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn iter(&self) -> SetIter<'_, K, C> {
+        SetIter(self.list.keys())
+    }
+
+    /// Returns an iterator over the keys that lie in `range`, in order.
+    pub fn range<Q, R>(&self, range: R) -> SetRange<'_, K, C>
+    where
+        Q: ?Sized,
+        R: RangeBounds<Q>,
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        SetRange(self.list.range(range))
+    }
+
+    /// Returns an iterator over the keys in `self` or `other`, in order.
+    ///
+    /// Both sets are walked side by side once, so this takes
+    /// `O(self.len() + other.len())`. `other` must order its keys the same
+    /// way as `self`, whose comparator decides.
+    pub fn union<'a>(&'a self, other: &'a SkipSet<K, C>) -> Union<'a, K, C> {
+        Union(MergeIter::new(self, other))
+    }
+
+    /// Returns an iterator over the keys in both `self` and `other`, in
+    /// order. Walks both sets once, like [`union`](Self::union).
+    pub fn intersection<'a>(&'a self, other: &'a SkipSet<K, C>) -> Intersection<'a, K, C> {
+        Intersection(MergeIter::new(self, other))
+    }
+
+    /// Returns an iterator over the keys in `self` but not in `other`, in
+    /// order. Walks both sets once, like [`union`](Self::union).
+    pub fn difference<'a>(&'a self, other: &'a SkipSet<K, C>) -> Difference<'a, K, C> {
+        Difference(MergeIter::new(self, other))
+    }
+}
+
+impl<K> FromIterator<K> for SkipSet<K>
+where
+    K: Ord,
+{
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        let mut set = SkipSet::new();
+
+        for key in iter {
+            set.insert(key);
+        }
+
+        set
+    }
+}
+
+impl<'a, K, C> IntoIterator for &'a SkipSet<K, C>
+where
+    C: Comparator<K>,
+{
+    type Item = &'a K;
+    type IntoIter = SetIter<'a, K, C>;
+
+    fn into_iter(self) -> SetIter<'a, K, C> {
+        self.iter()
+    }
+}
+
+/// An iterator over the keys of a [`SkipSet`], in order.
+pub struct SetIter<'a, K, C>(Keys<'a, K, (), C>)
+where
+    C: Comparator<K>;
+
+impl<'a, K, C> Iterator for SetIter<'a, K, C>
+where
+    C: Comparator<K>,
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, C> DoubleEndedIterator for SetIter<'_, K, C>
+where
+    C: Comparator<K>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
+    }
+}
+
+impl<K, C> ExactSizeIterator for SetIter<'_, K, C> where C: Comparator<K> {}
+
+impl<K, C> FusedIterator for SetIter<'_, K, C> where C: Comparator<K> {}
+
+/// An iterator over the keys of a [`SkipSet`] that lie in a range, in order.
+/// Created by [`SkipSet::range`].
+pub struct SetRange<'a, K, C>(Range<'a, K, (), C>)
+where
+    C: Comparator<K>;
+
+impl<'a, K, C> Iterator for SetRange<'a, K, C>
+where
+    C: Comparator<K>,
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        self.0.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, C> DoubleEndedIterator for SetRange<'_, K, C>
+where
+    C: Comparator<K>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(key, _)| key)
+    }
+}
+
+impl<K, C> ExactSizeIterator for SetRange<'_, K, C> where C: Comparator<K> {}
+
+impl<K, C> FusedIterator for SetRange<'_, K, C> where C: Comparator<K> {}
+
+/// Walks two sets side by side, yielding each key once along with which of
+/// the sets hold it.
+struct MergeIter<'a, K, C>
+where
+    C: Comparator<K>,
+{
+    a: Peekable<SetIter<'a, K, C>>,
+    b: Peekable<SetIter<'a, K, C>>,
+    cmp: &'a C,
+}
+
+impl<'a, K, C> MergeIter<'a, K, C>
+where
+    C: Comparator<K>,
+{
+    fn new(a: &'a SkipSet<K, C>, b: &'a SkipSet<K, C>) -> Self {
+        MergeIter {
+            a: a.iter().peekable(),
+            b: b.iter().peekable(),
+            cmp: &a.list.key_cmp,
+        }
+    }
+}
+
+impl<'a, K, C> Iterator for MergeIter<'a, K, C>
+where
+    C: Comparator<K>,
+{
+    /// The key, whether `a` holds it and whether `b` holds it.
+    type Item = (&'a K, bool, bool);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let order = match (self.a.peek(), self.b.peek()) {
+            (None, None) => return None,
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(a), Some(b)) => self.cmp.compare(a, b),
+        };
+
+        match order {
+            Ordering::Less => Some((self.a.next()?, true, false)),
+            Ordering::Greater => Some((self.b.next()?, false, true)),
+            Ordering::Equal => {
+                self.b.next();
+                Some((self.a.next()?, true, true))
+            }
+        }
+    }
+}
+
+/// An iterator over the keys in either of two [`SkipSet`]s, in order.
+/// Created by [`SkipSet::union`].
+pub struct Union<'a, K, C>(MergeIter<'a, K, C>)
+where
+    C: Comparator<K>;
+
+impl<'a, K, C> Iterator for Union<'a, K, C>
+where
+    C: Comparator<K>,
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        self.0.next().map(|(key, _, _)| key)
+    }
+}
+
+impl<K, C> FusedIterator for Union<'_, K, C> where C: Comparator<K> {}
+
+/// An iterator over the keys in both of two [`SkipSet`]s, in order.
+/// Created by [`SkipSet::intersection`].
+pub struct Intersection<'a, K, C>(MergeIter<'a, K, C>)
+where
+    C: Comparator<K>;
+
+impl<'a, K, C> Iterator for Intersection<'a, K, C>
+where
+    C: Comparator<K>,
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        loop {
+            // Once either set runs out no key can be in both.
+            if self.0.a.peek().is_none() || self.0.b.peek().is_none() {
+                return None;
+            }
+
+            if let (key, true, true) = self.0.next()? {
+                return Some(key);
+            }
+        }
+    }
+}
+
+impl<K, C> FusedIterator for Intersection<'_, K, C> where C: Comparator<K> {}
+
+/// An iterator over the keys in one [`SkipSet`] but not another, in order.
+/// Created by [`SkipSet::difference`].
+pub struct Difference<'a, K, C>(MergeIter<'a, K, C>)
+where
+    C: Comparator<K>;
+
+impl<'a, K, C> Iterator for Difference<'a, K, C>
+where
+    C: Comparator<K>,
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        loop {
+            self.0.a.peek()?;
+
+            if let (key, true, false) = self.0.next()? {
+                return Some(key);
+            }
+        }
+    }
+}
+
+impl<K, C> FusedIterator for Difference<'_, K, C> where C: Comparator<K> {}
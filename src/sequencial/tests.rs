@@ -0,0 +1,465 @@
+use std::ops::Bound::Included;
+
+use rand::{seq::SliceRandom, thread_rng};
+
+use super::*;
+use crate::ordered_map::tests::{
+    test_ordered_map, test_ordered_map_config, test_ordered_map_drop, test_ordered_map_navigate,
+    test_ordered_map_range, test_ordered_map_unit_drop, test_ordered_map_with_cmp, Navigate,
+};
+
+#[test]
+fn test_sequencial_ordered_map() {
+    test_ordered_map(SkipList::new());
+}
+
+#[test]
+fn test_sequencial_ordered_map_drop() {
+    test_ordered_map_drop(SkipList::new);
+}
+
+#[test]
+fn test_sequencial_ordered_map_unit_drop() {
+    test_ordered_map_unit_drop(SkipList::new);
+}
+
+#[test]
+fn test_sequencial_ordered_map_with_cmp() {
+    test_ordered_map_with_cmp(SkipList::with_cmp);
+}
+
+#[test]
+fn test_sequencial_ordered_map_config() {
+    test_ordered_map_config(SkipList::with_config);
+}
+
+#[test]
+fn test_sequencial_ordered_map_range() {
+    test_ordered_map_range(SkipList::new(), |map, range| {
+        map.range(range).map(|(key, _)| *key).collect()
+    });
+}
+
+#[test]
+fn test_sequencial_ordered_map_navigate() {
+    test_ordered_map_navigate(SkipList::new(), |map, query| {
+        let entry = match query {
+            Navigate::Floor(key) => map.floor(&key),
+            Navigate::Ceiling(key) => map.ceiling(&key),
+            Navigate::Lower(key) => map.lower(&key),
+            Navigate::Higher(key) => map.higher(&key),
+            Navigate::LowerBound(key) => map.lower_bound(&key),
+            Navigate::UpperBound(key) => map.upper_bound(&key),
+            Navigate::First => map.first(),
+            Navigate::Last => map.last(),
+        };
+
+        entry.map(|(key, value)| (*key, *value))
+    });
+}
+
+#[test]
+fn test_sequencial_borrowed_keys() {
+    let mut strings = SkipList::new();
+    assert!(strings.add("apple".to_string(), 1));
+    assert_eq!(strings.get("apple"), Some(&1));
+    assert_eq!(strings.remove("apple"), Some(1));
+    assert_eq!(strings.get("apple"), None);
+}
+
+#[test]
+fn test_sequencial_iter() {
+    let length = 1000;
+
+    let mut numbers = (0..length).collect::<Vec<_>>();
+    numbers.shuffle(&mut thread_rng());
+
+    let mut skiplist = SkipList::new();
+
+    for i in numbers.iter() {
+        skiplist.add(*i, *i * 10);
+    }
+
+    let sorted = (0..length).map(|i| (i, i * 10)).collect::<Vec<_>>();
+
+    let pairs = skiplist.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>();
+    assert_eq!(pairs, sorted);
+    assert_eq!(skiplist.iter().len(), length);
+    assert_eq!(skiplist.keys().len(), length);
+    assert_eq!(skiplist.values().len(), length);
+
+    let reversed = skiplist
+        .iter()
+        .rev()
+        .map(|(k, v)| (*k, *v))
+        .collect::<Vec<_>>();
+    assert_eq!(reversed, sorted.iter().rev().copied().collect::<Vec<_>>());
+
+    assert!(skiplist.keys().copied().eq(0..length));
+    assert!(skiplist.values().copied().eq((0..length).map(|i| i * 10)));
+
+    // Both ends meet in the middle without yielding an entry twice.
+    let mut iter = skiplist.keys();
+    let mut seen = Vec::new();
+    while let (Some(front), back) = (iter.next(), iter.next_back()) {
+        seen.push(*front);
+        seen.extend(back);
+        assert_eq!(iter.len(), length - seen.len());
+    }
+    seen.sort();
+    assert!(seen.into_iter().eq(0..length));
+
+    let mut iter_mut = skiplist.iter_mut();
+    assert_eq!(iter_mut.len(), length);
+    iter_mut.next_back();
+    assert_eq!(iter_mut.len(), length - 1);
+
+    for (k, v) in skiplist.iter_mut() {
+        *v += k;
+    }
+    assert!(skiplist.values().copied().eq((0..length).map(|i| i * 11)));
+
+    let mut range = skiplist.range(10..20);
+    assert_eq!(range.len(), 10);
+    assert_eq!(range.next_back().map(|(k, _)| *k), Some(19));
+    assert_eq!(range.len(), 9);
+    assert!(range.rev().map(|(k, _)| *k).eq((10..19).rev()));
+
+    let mut into_iter = skiplist.into_iter();
+    assert_eq!(into_iter.len(), length);
+    assert_eq!(into_iter.next(), Some((0, 0)));
+    assert_eq!(into_iter.next_back(), Some((length - 1, (length - 1) * 11)));
+    assert_eq!(into_iter.len(), length - 2);
+    assert!(into_iter.eq((1..length - 1).map(|i| (i, i * 11))));
+
+    let mut reversed = SkipList::with_cmp(|k1: &usize, k2: &usize| k2.cmp(k1));
+    for i in numbers {
+        reversed.add(i, ());
+    }
+    assert!(reversed.keys().copied().eq((0..length).rev()));
+    assert_eq!(reversed.range((Included(20), Included(10))).len(), 11);
+    assert_eq!(reversed.range(10..20).len(), 0);
+}
+
+#[test]
+fn test_sequencial_entry() {
+    let mut counts = SkipList::new();
+
+    for word in "a b a c b a".split(' ') {
+        *counts.entry(word).or_insert(0) += 1;
+    }
+
+    assert!(counts.iter().eq([(&"a", &3), (&"b", &2), (&"c", &1)]));
+
+    counts
+        .entry("a")
+        .and_modify(|count| *count *= 10)
+        .or_insert(0);
+    counts
+        .entry("d")
+        .and_modify(|count| *count *= 10)
+        .or_insert(4);
+    assert_eq!(counts.get("a"), Some(&30));
+    assert_eq!(counts.get("d"), Some(&4));
+
+    // The default is only built for a vacant entry.
+    counts.entry("a").or_insert_with(|| unreachable!());
+    assert_eq!(*counts.entry("e").or_insert_with(|| 5), 5);
+    assert_eq!(*counts.entry("f").or_default(), 0);
+    assert_eq!(counts.len(), 6);
+
+    match counts.entry("b") {
+        Entry::Occupied(mut entry) => {
+            assert_eq!(entry.key(), &"b");
+            assert_eq!(entry.insert(20), 2);
+            assert_eq!(entry.remove(), 20);
+        }
+        Entry::Vacant(_) => unreachable!(),
+    }
+
+    match counts.entry("g") {
+        Entry::Vacant(entry) => assert_eq!(entry.into_key(), "g"),
+        Entry::Occupied(_) => unreachable!(),
+    }
+
+    // An entry filled through `insert` can be emptied again.
+    let entry = counts.entry("h").insert(8);
+    assert_eq!(entry.get(), &8);
+    assert_eq!(entry.remove_entry(), ("h", 8));
+
+    assert_eq!(counts.entry("c").insert(3).remove(), 3);
+
+    assert!(counts.keys().eq(&["a", "d", "e", "f"]));
+    assert_eq!(counts.len(), 4);
+}
+
+#[test]
+fn test_sequencial_skipset() {
+    let mut set = SkipSet::new();
+
+    assert!(set.is_empty());
+    assert!(set.insert(3));
+    assert!(set.insert(1));
+    assert!(!set.insert(3));
+    assert!(set.insert(2));
+    assert_eq!(set.len(), 3);
+
+    assert!(set.contains(&1));
+    assert!(!set.contains(&4));
+    assert!(set.iter().copied().eq(1..4));
+    assert!(set.iter().rev().copied().eq((1..4).rev()));
+    assert!(set.range(2..).copied().eq(2..4));
+
+    assert!(set.remove(&2));
+    assert!(!set.remove(&2));
+    assert!(set.iter().copied().eq([1, 3]));
+
+    let evens = (0..20).step_by(2).collect::<SkipSet<_>>();
+    let threes = (0..20).step_by(3).collect::<SkipSet<_>>();
+
+    assert!(evens
+        .union(&threes)
+        .copied()
+        .eq((0..20).filter(|i| i % 2 == 0 || i % 3 == 0)));
+    assert!(evens.intersection(&threes).copied().eq([0, 6, 12, 18]));
+    assert!(evens.difference(&threes).copied().eq([2, 4, 8, 10, 14, 16]));
+    assert!(threes.difference(&evens).copied().eq([3, 9, 15]));
+
+    let empty = SkipSet::new();
+    assert!(evens.union(&empty).eq(&evens));
+    assert!(evens.intersection(&empty).next().is_none());
+    assert!(evens.difference(&empty).eq(&evens));
+    assert!(empty.difference(&evens).next().is_none());
+
+    // The merge follows the sets' own order, not `Ord`.
+    let reverse = |k1: &i32, k2: &i32| k2.cmp(k1);
+    let mut a = SkipSet::with_cmp(reverse);
+    let mut b = SkipSet::with_cmp(reverse);
+    for i in 0..6 {
+        a.insert(i);
+        b.insert(i + 3);
+    }
+    assert!(a.union(&b).copied().eq((0..9).rev()));
+    assert!(a.intersection(&b).copied().eq([5, 4, 3]));
+    assert!(a.difference(&b).copied().eq([2, 1, 0]));
+}
+
+#[test]
+fn test_sequencial_multimap() {
+    let mut events = SkipMultiMap::new();
+
+    assert!(events.is_empty());
+
+    for (time, event) in [(2, "b"), (1, "a"), (2, "c"), (3, "d"), (2, "e")] {
+        events.insert(time, event);
+    }
+
+    assert_eq!(events.len(), 5);
+    assert!(events.get_all(&2).eq([&"b", &"c", &"e"]));
+    assert!(events.get_all(&2).rev().eq([&"e", &"c", &"b"]));
+    assert!(events.get_all(&4).next().is_none());
+    assert!(events
+        .iter()
+        .eq([(&1, &"a"), (&2, &"b"), (&2, &"c"), (&2, &"e"), (&3, &"d")]));
+
+    assert_eq!(events.remove_one(&2), Some("b"));
+    assert!(events.get_all(&2).eq([&"c", &"e"]));
+    assert_eq!(events.remove_all(&2), 2);
+    assert_eq!(events.remove_all(&2), 0);
+    assert_eq!(events.remove_one(&2), None);
+    assert!(events.iter().eq([(&1, &"a"), (&3, &"d")]));
+
+    // Many values for one key, removed from the front one at a time, keep
+    // the skiplist's levels consistent.
+    let mut multimap = SkipMultiMap::new();
+
+    for i in 0..1000 {
+        multimap.insert(i % 3, i);
+    }
+
+    for i in (0..1000).step_by(3) {
+        assert_eq!(multimap.remove_one(&0), Some(i));
+    }
+
+    assert!(multimap.get_all(&1).copied().eq((1..1000).step_by(3)));
+    assert_eq!(multimap.len(), 666);
+
+    let mut strings = SkipMultiMap::new();
+    strings.insert("apple".to_string(), 1);
+    strings.insert("apple".to_string(), 2);
+    assert!(strings.get_all("apple").eq([&1, &2]));
+}
+
+#[test]
+fn test_sequencial_index() {
+    let mut numbers = (0..2000).step_by(2).collect::<Vec<_>>();
+    numbers.shuffle(&mut thread_rng());
+
+    let mut skiplist = SkipList::new();
+
+    for i in numbers.iter() {
+        skiplist.add(*i, *i * 10);
+    }
+
+    // A sorted vector serves as the model the widths must agree with.
+    let mut model = (0..2000).step_by(2).collect::<Vec<_>>();
+
+    let check = |skiplist: &SkipList<usize, usize>, model: &Vec<usize>| {
+        for (index, key) in model.iter().enumerate() {
+            assert_eq!(skiplist.get_by_index(index), Some((key, &(key * 10))));
+            assert_eq!(skiplist.rank(key), index);
+            assert_eq!(skiplist.rank(&(key + 1)), index + 1);
+        }
+
+        assert_eq!(skiplist.get_by_index(model.len()), None);
+    };
+
+    check(&skiplist, &model);
+
+    for (start, end) in [(0, 2000), (10, 20), (11, 21), (500, 499), (1990, 3000)] {
+        let expected = model
+            .iter()
+            .filter(|key| (start..end).contains(*key))
+            .count();
+        assert_eq!(skiplist.len_in_range(start..end), expected);
+
+        let expected = model
+            .iter()
+            .filter(|key| (start..=end).contains(*key))
+            .count();
+        assert_eq!(skiplist.len_in_range(start..=end), expected);
+    }
+    assert_eq!(skiplist.len_in_range(..), model.len());
+    assert_eq!(skiplist.len_in_range(..10), 5);
+
+    // Every way of adding and removing entries has to keep the widths right.
+    for (round, &key) in numbers[..300].iter().enumerate() {
+        match round % 5 {
+            0 => {
+                let index = key % model.len();
+                let removed = model.remove(index);
+                assert_eq!(
+                    skiplist.remove_by_index(index),
+                    Some((removed, removed * 10))
+                );
+            }
+            1 => {
+                if let Ok(index) = model.binary_search(&key) {
+                    model.remove(index);
+                    assert_eq!(skiplist.remove(&key), Some(key * 10));
+                }
+            }
+            2 => {
+                let key = key + 1;
+                model.insert(model.binary_search(&key).unwrap_err(), key);
+                skiplist.insert(key, key * 10);
+            }
+            3 => {
+                let first = model.remove(0);
+                assert_eq!(skiplist.remove_by_index(0), Some((first, first * 10)));
+            }
+            _ => {
+                if let Entry::Occupied(entry) = skiplist.entry(key) {
+                    model.remove(model.binary_search(&key).unwrap());
+                    entry.remove();
+                }
+            }
+        }
+    }
+
+    check(&skiplist, &model);
+    assert_eq!(skiplist.remove_by_index(model.len()), None);
+}
+
+#[test]
+fn test_sequencial_cursor() {
+    let mut skiplist = SkipList::<usize, usize>::new();
+
+    for i in (0..1000).step_by(4) {
+        skiplist.add(i, i);
+    }
+
+    // Merge the numbers 2 past each multiple of 4 in during one walk, and
+    // drop the multiples of 12 on the way.
+    let mut cursor = skiplist.cursor_front_mut();
+
+    while let Some((key, value)) = cursor.current() {
+        let key = *key;
+        *value += 1;
+
+        assert_eq!(cursor.index(), key / 4 * 2 - key.div_ceil(12));
+        assert_eq!(
+            cursor.peek_next().map(|(key, _)| *key),
+            Some(key + 4).filter(|k| *k < 1000)
+        );
+        assert_eq!(cursor.insert_after(key, 0), Err((key, 0)));
+        if key + 4 < 1000 {
+            assert_eq!(cursor.insert_after(key + 4, 0), Err((key + 4, 0)));
+        }
+        assert!(cursor.insert_after(key + 2, key + 2).is_ok());
+
+        if key % 12 == 0 {
+            assert_eq!(cursor.remove_current(), Some((key, key + 1)));
+        } else {
+            cursor.move_next();
+        }
+
+        cursor.move_next();
+    }
+
+    assert_eq!(cursor.index(), 500 - 84);
+    assert!(cursor.peek_next().is_none());
+    assert!(cursor.remove_current().is_none());
+    assert!(cursor.insert_after(1000, 1000).is_err());
+    assert!(cursor.insert_before(1000, 1000).is_ok());
+    assert!(cursor.insert_before(999, 999).is_err());
+    cursor.move_next();
+    assert_eq!(cursor.index(), 500 - 84 + 1);
+
+    let expected = (0..=1000)
+        .step_by(2)
+        .filter(|key| key % 12 != 0)
+        .map(|key| {
+            (
+                key,
+                if key % 4 == 0 && key < 1000 {
+                    key + 1
+                } else {
+                    key
+                },
+            )
+        })
+        .collect::<Vec<_>>();
+
+    assert!(skiplist
+        .iter()
+        .map(|(k, v)| (*k, *v))
+        .eq(expected.iter().copied()));
+    assert_eq!(skiplist.len(), expected.len());
+
+    // The widths have to agree with what the cursor did.
+    for (index, (key, _)) in expected.iter().enumerate() {
+        assert_eq!(skiplist.rank(key), index);
+        assert_eq!(skiplist.get_by_index(index).map(|(k, _)| *k), Some(*key));
+    }
+
+    let mut cursor = skiplist.cursor_mut_at(&501);
+    assert_eq!(cursor.current().map(|(key, _)| *key), Some(502));
+    assert_eq!(cursor.index(), skiplist_rank(&expected, 502));
+    assert!(cursor.insert_before(501, 501).is_ok());
+    assert!(cursor.insert_before(500, 500).is_err());
+    assert_eq!(cursor.remove_current(), Some((502, 502)));
+    assert_eq!(cursor.current().map(|(key, _)| *key), Some(506));
+    assert_eq!(skiplist.get(&501), Some(&501));
+    assert_eq!(skiplist.rank(&506), skiplist_rank(&expected, 502) + 1);
+
+    let mut empty = SkipList::<usize, usize>::new();
+    let mut cursor = empty.cursor_front_mut();
+    assert!(cursor.current().is_none());
+    assert!(cursor.insert_before(1, 1).is_ok());
+    assert_eq!(empty.first(), Some((&1, &1)));
+}
+
+fn skiplist_rank(entries: &[(usize, usize)], key: usize) -> usize {
+    entries.iter().filter(|(k, _)| *k < key).count()
+}
@@ -0,0 +1,70 @@
+use std::cell::Cell;
+use std::sync::atomic::{AtomicIsize, AtomicUsize, Ordering::*};
+use std::thread::available_parallelism;
+
+/// A counter split over several cache lines, so that threads updating it at
+/// the same time rarely touch the same one.
+///
+/// Each thread always updates the same stripe and [`sum`](Self::sum) adds
+/// them all up. The sum is exact once every update has returned, but a sum
+/// taken while updates are running may include some of them and not others.
+pub(crate) struct StripedCounter {
+    stripes: Box<[Stripe]>,
+}
+
+// Keeps every stripe on its own cache line.
+#[repr(align(128))]
+#[derive(Default)]
+struct Stripe(AtomicIsize);
+
+impl StripedCounter {
+    pub fn new() -> Self {
+        let stripes = available_parallelism()
+            .map_or(1, |n| n.get())
+            .next_power_of_two();
+
+        StripedCounter {
+            stripes: (0..stripes).map(|_| Stripe::default()).collect(),
+        }
+    }
+
+    pub fn increment(&self) {
+        self.stripe().fetch_add(1, Relaxed);
+    }
+
+    pub fn decrement(&self) {
+        self.stripe().fetch_sub(1, Relaxed);
+    }
+
+    /// Adds up the stripes. A remove may be counted before the add it undoes,
+    /// so a sum that would be negative is reported as 0.
+    pub fn sum(&self) -> usize {
+        let sum: isize = self
+            .stripes
+            .iter()
+            .map(|stripe| stripe.0.load(Relaxed))
+            .sum();
+        sum.max(0) as usize
+    }
+
+    fn stripe(&self) -> &AtomicIsize {
+        &self.stripes[thread_index() & (self.stripes.len() - 1)].0
+    }
+}
+
+/// A small number identifying the current thread, handed out in turn.
+fn thread_index() -> usize {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+    thread_local! {
+        static INDEX: Cell<Option<usize>> = const { Cell::new(None) };
+    }
+
+    INDEX.with(|index| {
+        index.get().unwrap_or_else(|| {
+            let next = NEXT.fetch_add(1, Relaxed);
+            index.set(Some(next));
+            next
+        })
+    })
+}
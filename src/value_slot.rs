@@ -0,0 +1,84 @@
+use std::mem::{self, needs_drop, size_of};
+use std::ptr::NonNull;
+use std::sync::atomic::Ordering;
+
+use crossbeam_epoch::{Atomic, CompareExchangeError, Guard, Owned, Pointer, Shared};
+
+/// The value slot of an entry of a concurrent list, through which its value
+/// is loaded and swapped.
+///
+/// Values that are zero-sized and have nothing to drop, such as the `()` of
+/// a set, are all alike, so entries holding them store no slot at all. The
+/// slot then always reads as a value that is present, every swap succeeds
+/// and the values swapped in are forgotten; the lists tell removed entries
+/// apart by their marks.
+pub(crate) struct ValueSlot<'a, V> {
+    atomic: Option<&'a Atomic<V>>,
+}
+
+impl<'a, V> ValueSlot<'a, V> {
+    /// Whether entries holding `V` store a slot.
+    pub const STORED: bool = size_of::<V>() != 0 || needs_drop::<V>();
+
+    /// Wraps the slot stored at `atomic`, which is only read if
+    /// [`STORED`](Self::STORED).
+    ///
+    /// # Safety
+    ///
+    /// If the slot is stored, `atomic` must point to it for as long as `'a`.
+    pub unsafe fn new(atomic: *const Atomic<V>) -> Self {
+        ValueSlot {
+            atomic: Self::STORED.then(|| &*atomic),
+        }
+    }
+
+    pub fn load<'g>(&self, order: Ordering, guard: &'g Guard) -> Shared<'g, V> {
+        match self.atomic {
+            Some(atomic) => atomic.load(order, guard),
+            None => Self::unstored(),
+        }
+    }
+
+    pub fn store<P>(&self, new: P, order: Ordering)
+    where
+        P: Pointer<V>,
+    {
+        match self.atomic {
+            Some(atomic) => atomic.store(new, order),
+            None => mem::forget(new),
+        }
+    }
+
+    pub fn swap<'g, P>(&self, new: P, order: Ordering, guard: &'g Guard) -> Shared<'g, V>
+    where
+        P: Pointer<V>,
+    {
+        match self.atomic {
+            Some(atomic) => atomic.swap(new, order, guard),
+            None => {
+                mem::forget(new);
+                Self::unstored()
+            }
+        }
+    }
+
+    pub fn compare_exchange<'g>(
+        &self,
+        current: Shared<'_, V>,
+        new: Owned<V>,
+        success: Ordering,
+        failure: Ordering,
+        guard: &'g Guard,
+    ) -> Result<Shared<'g, V>, CompareExchangeError<'g, V, Owned<V>>> {
+        match self.atomic {
+            Some(atomic) => atomic.compare_exchange(current, new, success, failure, guard),
+            None => Ok(new.into_shared(guard)),
+        }
+    }
+
+    /// A value that is not stored. Being zero-sized, it may live at any
+    /// aligned address, and dropping it does nothing.
+    fn unstored<'g>() -> Shared<'g, V> {
+        Shared::from(NonNull::<V>::dangling().as_ptr() as *const V)
+    }
+}